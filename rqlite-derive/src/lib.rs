@@ -0,0 +1,189 @@
+//! Derive macros for the rqlite crate.
+//!
+//! Use them through the `derive` feature of rqlite instead of depending on this crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, LitInt, LitStr};
+
+mod query;
+
+/// Derive `rqlite::FromRow`
+///
+/// Named fields are read from the column with the same name,
+/// tuple fields from the column at the same position.
+///
+/// Field attributes:
+/// - `#[rqlite(rename = "column")]` read field from another column name
+/// - `#[rqlite(index = 2)]` read field from the column at a position
+#[proc_macro_derive(FromRow, attributes(rqlite))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_from_row(&input) {
+        Ok(v)  => v.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+/// Where a field is read from
+enum Column {
+    Name(String),
+    Index(usize)
+}
+
+fn expand_from_row(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let data = match &input.data {
+        Data::Struct(v) => v,
+        _ => return Err(syn::Error::new_spanned(input, "FromRow can only be derived for structs"))
+    };
+
+    let body = match &data.fields {
+        Fields::Named(fields) => {
+            let mut values = Vec::with_capacity(fields.named.len());
+            for field in &fields.named {
+                let ident = field.ident.as_ref().unwrap();
+                let default = ident.to_string().trim_start_matches("r#").to_owned();
+                let value = column_value(column(field, Column::Name(default))?);
+                values.push(quote! { #ident: #value });
+            }
+            quote! { Self { #(#values),* } }
+        },
+        Fields::Unnamed(fields) => {
+            let mut values = Vec::with_capacity(fields.unnamed.len());
+            for (i, field) in fields.unnamed.iter().enumerate() {
+                values.push(column_value(column(field, Column::Index(i))?));
+            }
+            quote! { Self(#(#values),*) }
+        },
+        Fields::Unit => quote! { Self }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::rqlite::FromRow for #name #ty_generics #where_clause {
+            fn from_row(row: &::rqlite::Row) -> ::std::result::Result<Self, ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::std::result::Result::Ok(#body)
+            }
+        }
+    })
+}
+
+/// Read `#[rqlite(...)]` attributes of a field
+fn column(field: &Field, default: Column) -> syn::Result<Column> {
+    let mut column = default;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("rqlite")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                column = Column::Name(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("index") {
+                column = Column::Index(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown rqlite attribute, expected `rename` or `index`"))
+            }
+        })?;
+    }
+    Ok(column)
+}
+
+/// Expression reading a column from `row`
+fn column_value(column: Column) -> TokenStream2 {
+    match column {
+        Column::Index(i) => quote! { row.get(#i)? },
+        Column::Name(name) => quote! { row.get_by_name(#name)? }
+    }
+}
+
+/// Build a `rqlite::migrate::Migrator` from the `.sql` files of a directory
+///
+/// The path is relative to the crate root, files are embedded at compile time
+/// and added in file name order. `name.up.sql` and `name.down.sql` make a reversible
+/// migration, versions are file names without `.up` or `.down`.
+#[proc_macro]
+pub fn include_migrations(input: TokenStream) -> TokenStream {
+    let dir = parse_macro_input!(input as LitStr);
+    match expand_include_migrations(&dir) {
+        Ok(v)  => v.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+fn expand_include_migrations(dir: &LitStr) -> syn::Result<TokenStream2> {
+    let root = std::env::var("CARGO_MANIFEST_DIR")
+            .map_err(|_| syn::Error::new_spanned(dir, "CARGO_MANIFEST_DIR is not set"))?;
+    let path = std::path::Path::new(&root).join(dir.value());
+    let entries = std::fs::read_dir(&path)
+            .map_err(|e| syn::Error::new_spanned(dir, format!("Cannot read {}: {}", path.display(), e)))?;
+
+    // version -> (up, down)
+    let mut migrations: std::collections::BTreeMap<String, (Option<String>, Option<String>)> = Default::default();
+    for entry in entries {
+        let entry = entry.map_err(|e| syn::Error::new_spanned(dir, e.to_string()))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let file = entry.path().to_string_lossy().into_owned();
+        if let Some(stem) = name.strip_suffix(".down.sql") {
+            migrations.entry(format!("{}.sql", stem)).or_default().1 = Some(file);
+        } else if let Some(stem) = name.strip_suffix(".up.sql") {
+            migrations.entry(format!("{}.sql", stem)).or_default().0 = Some(file);
+        } else if name.ends_with(".sql") {
+            migrations.entry(name).or_default().0 = Some(file);
+        }
+    }
+
+    let mut adds = Vec::new();
+    for (version, (up, down)) in migrations {
+        let up = match up {
+            Some(v) => v,
+            None => return Err(syn::Error::new_spanned(dir, format!("Migration {} has no up script", version)))
+        };
+        adds.push(match down {
+            Some(down) => quote! { migrator.add_reversible(#version, include_str!(#up), include_str!(#down)); },
+            None => quote! { migrator.add(#version, include_str!(#up)); }
+        });
+    }
+    Ok(quote! {
+        {
+            let mut migrator = ::rqlite::migrate::Migrator::new();
+            #(#adds)*
+            migrator
+        }
+    })
+}
+
+/// Build a `rqlite::TypedQuery` from a sql literal and its parameters
+///
+/// The number of `?` placeholders is checked against the arguments at compile time,
+/// arguments are converted like the ones of `par!`.
+///
+/// Rows are read into a generated struct with a field per result column, named after the
+/// column or its alias. Field types come from `rqlite-schema.json` in the crate root when it
+/// exists, mapping each table to its column definitions:
+/// ```json
+/// { "foo": ["id INTEGER NOT NULL", "name TEXT"] }
+/// ```
+/// Queries are then checked against the schema: unknown tables or columns fail to compile,
+/// and `SELECT *` is expanded. Columns without a known type are `rqlite::Value`,
+/// an alias like `AS "n: u32"` sets the type of a column.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// let foos = rqlite::query!("SELECT id, name FROM foo WHERE id > ?", 1).fetch_all(&conn).await?;
+/// for foo in foos {
+///     println!("{} {:?}", foo.id, foo.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn query(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as query::QueryInput);
+    match query::expand_query(&input) {
+        Ok(v)  => v.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
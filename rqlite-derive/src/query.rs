@@ -0,0 +1,500 @@
+//! `query!` macro: sql is parsed at compile time to count placeholders and find result columns
+use std::collections::HashMap;
+use std::path::Path;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, Ident, LitStr, Token, Type};
+
+/// Schema cache, looked up in the crate root
+const SCHEMA_FILE: &str = "rqlite-schema.json";
+
+pub(crate) struct QueryInput {
+    sql: LitStr,
+    args: Vec<Expr>
+}
+
+impl Parse for QueryInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let sql: LitStr = input.parse()?;
+        let mut args = Vec::new();
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            args = Punctuated::<Expr, Token![,]>::parse_terminated(input)?.into_iter().collect();
+        }
+        Ok(QueryInput { sql, args })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    /// Keyword or identifier, quoted identifiers are unquoted
+    Word(String, bool),
+    /// String literal, number, operator...
+    Other(char),
+    /// Placeholder, with its number for `?NNN`
+    Param(Option<usize>),
+    Named(String)
+}
+
+impl Tok {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Tok::Word(w, false) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn ident(&self) -> Option<&str> {
+        match self {
+            Tok::Word(w, _) => Some(w),
+            _ => None
+        }
+    }
+}
+
+/// Column of the cached schema
+struct SchemaColumn {
+    name: String,
+    type_name: String,
+    not_null: bool
+}
+
+/// Columns of each table, in order
+type Schema = HashMap<String, Vec<SchemaColumn>>;
+
+/// Table of the FROM clause
+struct FromTable {
+    alias: String,
+    table: String,
+    /// Right side of an outer join
+    nullable: bool
+}
+
+/// Result column of the query
+struct ResultColumn {
+    name: String,
+    ty: TokenStream2
+}
+
+pub(crate) fn expand_query(input: &QueryInput) -> syn::Result<TokenStream2> {
+    let sql = input.sql.value();
+    let err = |message: String| syn::Error::new_spanned(&input.sql, message);
+    let tokens = tokenize(&sql).map_err(err)?;
+
+    let expected = placeholders(&tokens).map_err(err)?;
+    if expected != input.args.len() {
+        return Err(err(format!("Query has {} placeholders but {} arguments were given", expected, input.args.len())));
+    }
+
+    let (schema, schema_path) = load_schema().map_err(err)?;
+    let columns = result_columns(&tokens, schema.as_ref()).map_err(err)?;
+
+    let names: Vec<Ident> = columns.iter().map(|c| field_ident(&c.name)).collect::<Result<_, _>>().map_err(err)?;
+    let types = columns.iter().map(|c| &c.ty);
+    let ids = 0..columns.len();
+    let args = &input.args;
+    // recompile when the schema changes
+    let track = schema_path.map(|path| quote! { const _: &[u8] = include_bytes!(#path); });
+    Ok(quote! {
+        {
+            #track
+            #[derive(Clone, Debug)]
+            struct Row {
+                #(pub #names: #types,)*
+            }
+            impl ::rqlite::FromRow for Row {
+                fn from_row(row: &::rqlite::Row) -> ::std::result::Result<Self, ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                    let _ = row;
+                    Ok(Row { #(#names: row.get(#ids)?,)* })
+                }
+            }
+            ::rqlite::TypedQuery::<Row>::new(#sql, ::rqlite::par!(#(#args),*))
+        }
+    })
+}
+
+/// Split `sql` into tokens
+///
+/// Quotes, comments and parameters are read like `rqlite`'s own scanner (`src/sql.rs`),
+/// which a proc-macro crate can't depend on.
+fn tokenize(sql: &str) -> Result<Vec<Tok>, String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            },
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                // `/*/` doesn't close the comment
+                i += 3;
+                while i < chars.len() && !(chars[i - 1] == '*' && chars[i] == '/') {
+                    i += 1;
+                }
+                i += 1;
+            },
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err("Unterminated quote in query".to_owned()),
+                        Some(&v) if v == close => {
+                            // doubled quote is an escaped quote
+                            if close != ']' && chars.get(i + 1) == Some(&close) {
+                                value.push(close);
+                                i += 2;
+                                continue;
+                            }
+                            i += 1;
+                            break;
+                        },
+                        Some(&v) => {
+                            value.push(v);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push(if c == '\'' { Tok::Other('\'') } else { Tok::Word(value, true) });
+            },
+            '?' => {
+                i += 1;
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                tokens.push(Tok::Param(number.parse().ok()));
+            },
+            ':' | '@' | '$' if chars.get(i + 1).is_some_and(|v| v.is_alphabetic() || *v == '_') => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Tok::Named(chars[start..i].iter().collect()));
+            },
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+                    i += 1;
+                }
+                tokens.push(Tok::Word(chars[start..i].iter().collect(), false));
+            },
+            c if c.is_ascii_digit() => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                    i += 1;
+                }
+                tokens.push(Tok::Other('0'));
+            },
+            c => {
+                tokens.push(Tok::Other(c));
+                i += 1;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Number of parameters expected by the query
+fn placeholders(tokens: &[Tok]) -> Result<usize, String> {
+    let mut count = 0;
+    for token in tokens {
+        match token {
+            Tok::Param(None) => count += 1,
+            Tok::Param(Some(n)) => count = count.max(*n),
+            Tok::Named(name) => return Err(format!("Named parameter {} is not supported, use ?", name)),
+            _ => ()
+        }
+    }
+    Ok(count)
+}
+
+fn load_schema() -> Result<(Option<Schema>, Option<String>), String> {
+    let root = std::env::var("CARGO_MANIFEST_DIR").map_err(|_| "CARGO_MANIFEST_DIR is not set".to_owned())?;
+    let path = Path::new(&root).join(SCHEMA_FILE);
+    if !path.exists() {
+        return Ok((None, None));
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let tables: HashMap<String, Vec<String>> = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid schema in {}: {}", path.display(), e))?;
+    let mut schema = Schema::new();
+    for (table, definitions) in tables {
+        let mut columns = Vec::new();
+        for definition in definitions {
+            let tokens = tokenize(&definition)?;
+            let name = match tokens.first().and_then(|t| t.ident()) {
+                Some(v) => v.to_ascii_lowercase(),
+                None => return Err(format!("Invalid column {} of {} in {}", definition, table, SCHEMA_FILE))
+            };
+            let words: Vec<String> = tokens[1..].iter().filter_map(|t| t.ident()).map(|w| w.to_ascii_uppercase()).collect();
+            let not_null = words.windows(2).any(|w| w[0] == "NOT" && w[1] == "NULL");
+            let type_name = words.join(" ");
+            columns.push(SchemaColumn { name, type_name, not_null });
+        }
+        schema.insert(table.to_ascii_lowercase(), columns);
+    }
+    Ok((Some(schema), Some(path.to_string_lossy().into_owned())))
+}
+
+/// Keywords which are not aliases, or which can't precede an implicit alias
+const OPERATORS: &[&str] = &["AND", "OR", "NOT", "IS", "IN", "LIKE", "GLOB", "REGEXP", "MATCH", "BETWEEN", "CASE",
+                             "WHEN", "THEN", "ELSE", "COLLATE", "ESCAPE", "DISTINCT", "NULL", "END", "CAST", "EXISTS"];
+
+/// Columns returned by a SELECT, other statements return none
+fn result_columns(tokens: &[Tok], schema: Option<&Schema>) -> Result<Vec<ResultColumn>, String> {
+    // common table expressions are not in the schema
+    let schema = if tokens.first().is_some_and(|t| t.is_keyword("WITH")) { None } else { schema };
+    // skip a WITH clause: the main SELECT is the first one at depth 0 after it
+    let mut depth = 0;
+    let mut start = None;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Tok::Other('(') => depth += 1,
+            Tok::Other(')') => depth -= 1,
+            t if depth == 0 && t.is_keyword("SELECT") => {
+                start = Some(i + 1);
+                break;
+            },
+            t if depth == 0 && i == 0 && !t.is_keyword("WITH") => break,
+            _ => ()
+        }
+    }
+    let start = match start {
+        Some(v) => v,
+        None => return Ok(Vec::new())
+    };
+
+    let items = top_level(&tokens[start..], |t| t.is_keyword("FROM") || t.is_keyword("WHERE") || t.is_keyword("GROUP")
+            || t.is_keyword("ORDER") || t.is_keyword("LIMIT") || t.is_keyword("UNION") || t.is_keyword("EXCEPT")
+            || t.is_keyword("INTERSECT") || t.is_keyword("WINDOW") || t.is_keyword("HAVING"));
+    let tables = from_tables(&tokens[start..]);
+    if let Some(schema) = schema {
+        if let Some(t) = tables.iter().find(|t| !schema.contains_key(&t.table)) {
+            return Err(format!("Table {} is not in {}", t.table, SCHEMA_FILE));
+        }
+    }
+
+    let mut columns = Vec::new();
+    for (n, item) in items.iter().enumerate() {
+        let mut item: &[Tok] = item;
+        if n == 0 && item.first().is_some_and(|t| t.is_keyword("DISTINCT") || t.is_keyword("ALL")) {
+            item = &item[1..];
+        }
+        match item {
+            [Tok::Other('*')] => columns.extend(star(None, &tables, schema)?),
+            [Tok::Word(table, _), Tok::Other('.'), Tok::Other('*')] => columns.extend(star(Some(table), &tables, schema)?),
+            _ => columns.push(result_column(item, &tables, schema)?)
+        }
+    }
+    Ok(columns)
+}
+
+/// Split `tokens` on commas at depth 0, until a token matching `stop` at depth 0
+fn top_level(tokens: &[Tok], stop: impl Fn(&Tok) -> bool) -> Vec<Vec<Tok>> {
+    let mut items = vec![Vec::new()];
+    let mut depth = 0;
+    for token in tokens {
+        match token {
+            Tok::Other('(') => depth += 1,
+            Tok::Other(')') => depth -= 1,
+            Tok::Other(',') if depth == 0 => {
+                items.push(Vec::new());
+                continue;
+            },
+            t if depth == 0 && stop(t) => break,
+            _ => ()
+        }
+        items.last_mut().unwrap().push(token.clone());
+    }
+    items
+}
+
+/// Tables after FROM and JOIN of the first SELECT, in order
+fn from_tables(tokens: &[Tok]) -> Vec<FromTable> {
+    const CLAUSES: &[&str] = &["WHERE", "JOIN", "LEFT", "RIGHT", "FULL", "INNER", "CROSS", "NATURAL", "OUTER",
+                               "ON", "USING", "GROUP", "ORDER", "LIMIT", "WINDOW", "HAVING", "INDEXED", "NOT"];
+    let mut tables = Vec::new();
+    let mut depth = 0;
+    let mut in_from = false;
+    let mut nullable = false;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Tok::Other('(') => depth += 1,
+            Tok::Other(')') => depth -= 1,
+            _ => ()
+        }
+        if depth != 0 {
+            continue;
+        }
+        if token.is_keyword("UNION") || token.is_keyword("EXCEPT") || token.is_keyword("INTERSECT") {
+            break;
+        }
+        if token.is_keyword("LEFT") || token.is_keyword("RIGHT") || token.is_keyword("FULL") {
+            nullable = true;
+        }
+        let starts_table = token.is_keyword("FROM") || token.is_keyword("JOIN") || (in_from && *token == Tok::Other(','));
+        if token.is_keyword("FROM") {
+            in_from = true;
+        } else if token.is_keyword("WHERE") || token.is_keyword("GROUP") || token.is_keyword("ORDER") || token.is_keyword("LIMIT") {
+            in_from = false;
+        }
+        if !starts_table {
+            continue;
+        }
+        let mut j = i + 1;
+        let mut table = match tokens.get(j).and_then(|t| t.ident()) {
+            Some(v) => v.to_ascii_lowercase(),
+            None => continue
+        };
+        // schema.table
+        if tokens.get(j + 1) == Some(&Tok::Other('.')) {
+            if let Some(name) = tokens.get(j + 2).and_then(|t| t.ident()) {
+                table = name.to_ascii_lowercase();
+                j += 2;
+            }
+        }
+        let mut alias = table.clone();
+        let next = if tokens.get(j + 1).is_some_and(|t| t.is_keyword("AS")) { j + 2 } else { j + 1 };
+        if let Some(t) = tokens.get(next) {
+            if let Some(name) = t.ident() {
+                if !CLAUSES.iter().any(|c| t.is_keyword(c)) {
+                    alias = name.to_ascii_lowercase();
+                }
+            }
+        }
+        tables.push(FromTable { alias, table, nullable });
+        nullable = false;
+    }
+    tables
+}
+
+fn find_table<'a>(tables: &'a [FromTable], alias: &str) -> Result<&'a FromTable, String> {
+    tables.iter().find(|t| t.alias.eq_ignore_ascii_case(alias)).ok_or_else(|| format!("Unknown table {}", alias))
+}
+
+/// Columns of `*` or `table.*`
+fn star(table: Option<&String>, tables: &[FromTable], schema: Option<&Schema>) -> Result<Vec<ResultColumn>, String> {
+    let schema = schema.ok_or_else(|| format!("SELECT * needs the columns of {}", SCHEMA_FILE))?;
+    let selected: Vec<&FromTable> = match table {
+        Some(alias) => vec![find_table(tables, alias)?],
+        None => tables.iter().collect()
+    };
+    Ok(selected.into_iter()
+        .flat_map(|t| schema[&t.table].iter().map(move |c| ResultColumn { name: c.name.clone(), ty: column_type(c, t.nullable) }))
+        .collect())
+}
+
+fn result_column(item: &[Tok], tables: &[FromTable], schema: Option<&Schema>) -> Result<ResultColumn, String> {
+    let is_operator = |t: &Tok| OPERATORS.iter().any(|o| t.is_keyword(o));
+    let (expr, alias) = match item {
+        [expr @ .., as_, Tok::Word(alias, _)] if as_.is_keyword("AS") => (expr, Some(alias)),
+        // implicit alias, like `COUNT(*) n`
+        [expr @ .., last, alias_tok @ Tok::Word(alias, _)] if !is_operator(alias_tok) && !is_operator(last)
+                && matches!(last, Tok::Word(..) | Tok::Other(')') | Tok::Other('\'') | Tok::Other('0') | Tok::Param(_)) => {
+            (&item[..expr.len() + 1], Some(alias))
+        },
+        _ => (item, None)
+    };
+    // `name: Type` alias overrides the type
+    if let Some((name, ty)) = alias.and_then(|a| a.split_once(':')) {
+        let ty: Type = syn::parse_str(ty.trim()).map_err(|e| format!("Invalid type {}: {}", ty.trim(), e))?;
+        return Ok(ResultColumn { name: name.trim().to_owned(), ty: quote! { #ty } });
+    }
+
+    let column = match expr {
+        [Tok::Word(column, _)] => Some((None, column)),
+        [Tok::Word(table, _), Tok::Other('.'), Tok::Word(column, _)] => Some((Some(table), column)),
+        _ => None
+    };
+    let name = match (alias, column) {
+        (Some(alias), _) => alias.clone(),
+        (None, Some((_, column))) => column.clone(),
+        (None, None) => return Err("Expressions in the result need an alias, like `COUNT(*) AS n`".to_owned())
+    };
+    let ty = match (schema, column) {
+        (Some(schema), Some((table, column))) => {
+            let column = column.to_ascii_lowercase();
+            let candidates: Vec<&FromTable> = match table {
+                Some(alias) => vec![find_table(tables, alias)?],
+                None => tables.iter().collect()
+            };
+            let found: Vec<(&SchemaColumn, bool)> = candidates.into_iter()
+                    .filter_map(|t| schema[&t.table].iter().find(|c| c.name == column).map(|c| (c, t.nullable)))
+                    .collect();
+            match found.as_slice() {
+                [(c, nullable)] => column_type(c, *nullable),
+                [] => return Err(format!("Unknown column {}", column)),
+                _ => return Err(format!("Ambiguous column {}", column))
+            }
+        },
+        _ => quote! { ::rqlite::Value }
+    };
+    Ok(ResultColumn { name, ty })
+}
+/// Rust type of a column, following sqlite type affinity
+fn column_type(column: &SchemaColumn, nullable: bool) -> TokenStream2 {
+    let t = &column.type_name;
+    let ty = if t.contains("INT") {
+        quote! { i64 }
+    } else if t.contains("CHAR") || t.contains("CLOB") || t.contains("TEXT") {
+        quote! { ::std::string::String }
+    } else if t.contains("BLOB") {
+        quote! { ::std::vec::Vec<u8> }
+    } else if t.contains("REAL") || t.contains("FLOA") || t.contains("DOUB") {
+        quote! { f64 }
+    } else {
+        return quote! { ::rqlite::Value };
+    };
+    if column.not_null && !nullable {
+        ty
+    } else {
+        quote! { ::std::option::Option<#ty> }
+    }
+}
+
+fn field_ident(name: &str) -> Result<Ident, String> {
+    if syn::parse_str::<Ident>(name).is_ok() {
+        return Ok(format_ident!("{}", name));
+    }
+    // keywords like `type`
+    if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') && !name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(Ident::new_raw(name, Span::call_site()));
+    }
+    Err(format!("Column {} is not a valid field name, give it an alias", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(sql: &str) -> Result<usize, String> {
+        placeholders(&tokenize(sql)?)
+    }
+
+    #[test]
+    fn placeholders_are_counted() {
+        assert_eq!(count("SELECT ?, ?"), Ok(2));
+        assert_eq!(count("SELECT ?5, ?"), Ok(6));
+        assert_eq!(count("SELECT ?, ?1"), Ok(1));
+    }
+
+    #[test]
+    fn placeholders_in_strings_and_comments() {
+        assert_eq!(count("SELECT 'it''s ?', \"a\"\"?\", [?], ?"), Ok(1));
+        assert_eq!(count("SELECT ? -- ?\n, ? /*/ ? */"), Ok(2));
+    }
+
+    #[test]
+    fn named_parameters_are_rejected() {
+        assert!(count("SELECT :name").is_err());
+        assert!(count("SELECT @name").is_err());
+        assert!(count("SELECT $name").is_err());
+        assert_eq!(count("SELECT a$b, ? -- :name"), Ok(1));
+    }
+}
@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use crate::error::RqliteError;
+
+type TokenFuture = Pin<Box<dyn Future<Output = Result<String, Box<dyn Error + Send + Sync>>> + Send>>;
+type TokenRefresh = Arc<dyn Fn() -> TokenFuture + Send + Sync>;
+
+/// Credentials shared by clones of a connection, and by connections of a pool
+pub(crate) struct Auth {
+    /// Value of the Authorization header
+    header: RwLock<Option<String>>,
+    /// Callback providing a new bearer token when a request is rejected
+    refresh: RwLock<Option<TokenRefresh>>
+}
+
+impl Auth {
+    pub(crate) fn new(header: Option<String>) -> Auth {
+        Auth { header: RwLock::new(header), refresh: RwLock::new(None) }
+    }
+
+    pub(crate) fn basic_header(user: &str, pass: &str) -> String {
+        format!("Basic {}", base64::encode(format!("{}:{}", user, pass)))
+    }
+
+    pub(crate) fn header(&self) -> Option<String> {
+        self.header.read().unwrap().clone()
+    }
+
+    pub(crate) fn set_header(&self, header: String) {
+        *self.header.write().unwrap() = Some(header);
+    }
+
+    pub(crate) fn set_refresh<F, Fut>(&self, refresh: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, Box<dyn Error + Send + Sync>>> + Send + 'static
+    {
+        let refresh: TokenRefresh = Arc::new(move || Box::pin(refresh()));
+        *self.refresh.write().unwrap() = Some(refresh);
+    }
+
+    /// Get a new token from the refresh callback
+    ///
+    /// Returns false if there is no callback, `RqliteError::AuthError` if it failed
+    pub(crate) async fn refresh(&self) -> Result<bool, RqliteError> {
+        let refresh = self.refresh.read().unwrap().clone();
+        let refresh = match refresh {
+            Some(v) => v,
+            None => return Ok(false)
+        };
+        match refresh().await {
+            Ok(token) => {
+                self.set_header(format!("Bearer {}", token));
+                Ok(true)
+            },
+            Err(_) => Err(RqliteError::AuthError)
+        }
+    }
+}
+
+// don't print credentials
+impl std::fmt::Debug for Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Auth")
+    }
+}
@@ -0,0 +1,61 @@
+//! [bb8](https://docs.rs/bb8) connection manager, with the `bb8` feature
+//! ```no_run
+//! # use rqlite::*;
+//! # type Error = Box<dyn std::error::Error + Send + Sync>;
+//! # async fn run() -> Result<(), Error> {
+//! use rqlite::ConnectOptions;
+//! use rqlite::bb8::{Manager, Pool};
+//!
+//! let pool = Pool::builder()
+//!     .max_size(16)
+//!     .build(Manager::new(&ConnectOptions::new("127.0.0.1", 4001)))
+//!     .await?;
+//! let conn = pool.get().await?;
+//! conn.execute("INSERT INTO foo(name) VALUES (?)", par!("fiona")).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::Arc;
+use ::bb8::ManageConnection;
+use crate::auth::Auth;
+use crate::connect::{ConnectOptions, Connection};
+use crate::error::RqliteError;
+
+/// Bb8 pool of rqlite connections
+pub type Pool = ::bb8::Pool<Manager>;
+
+/// Creates connections, checks them with `/readyz` and detects closed sockets
+///
+/// Connections of a manager share credentials, see [`Connection::set_credentials()`].
+#[derive(Debug)]
+pub struct Manager {
+    options: ConnectOptions,
+    auth: Arc<Auth>
+}
+
+impl Manager {
+    /// Create a manager opening connections with `options`
+    pub fn new(options: &ConnectOptions) -> Manager {
+        Manager { options: options.clone(), auth: Arc::new(options.auth()) }
+    }
+}
+
+impl ManageConnection for Manager {
+    type Connection = Connection;
+    type Error = RqliteError;
+
+    async fn connect(&self) -> Result<Connection, RqliteError> {
+        self.options.connect_with_auth(self.auth.clone()).await
+    }
+
+    async fn is_valid(&self, conn: &mut Connection) -> Result<(), RqliteError> {
+        match conn.ready().await? {
+            true => Ok(()),
+            false => Err(RqliteError::Connection("Node not ready".to_owned()))
+        }
+    }
+
+    fn has_broken(&self, conn: &mut Connection) -> bool {
+        conn.is_broken()
+    }
+}
@@ -0,0 +1,100 @@
+//! Apply migrations of a directory to a rqlite node
+//!
+//! ```text
+//! rqlite-migrate [--dir <migrations>] [--url <url>] <status|up|down>
+//! ```
+//! The directory defaults to `./migrations`, without `--url` the connection is configured
+//! from `RQLITE_*` environment variables.
+use std::process::ExitCode;
+use std::error::Error;
+use rqlite::ConnectOptions;
+use rqlite::migrate::Migrator;
+
+const USAGE: &str = "Usage: rqlite-migrate [--dir <migrations>] [--url <url>] <status|up|down>";
+
+enum Command {
+    Status,
+    Up,
+    Down
+}
+
+struct Args {
+    dir: String,
+    url: Option<String>,
+    command: Command
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut dir = "./migrations".to_owned();
+    let mut url = None;
+    let mut command = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dir" => dir = args.next().ok_or("--dir needs a value")?,
+            "--url" => url = Some(args.next().ok_or("--url needs a value")?),
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            "status" => command = Some(Command::Status),
+            "up" => command = Some(Command::Up),
+            "down" => command = Some(Command::Down),
+            v => return Err(format!("Unknown argument {}\n{}", v, USAGE))
+        }
+    }
+    match command {
+        Some(command) => Ok(Args { dir, url, command }),
+        None => Err(USAGE.to_owned())
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let migrator = Migrator::from_dir(&args.dir)?;
+    let mut options = match &args.url {
+        Some(url) => ConnectOptions::from_url(url)?,
+        None => ConnectOptions::from_env()?
+    };
+    let conn = options.connect().await?;
+    match args.command {
+        Command::Status => {
+            let applied = migrator.applied(&conn).await?;
+            for migration in migrator.migrations() {
+                let state = if applied.contains(&migration.version) { "applied" } else { "pending" };
+                println!("{:<8} {}", state, migration.version);
+            }
+            for version in applied.iter().filter(|v| !migrator.migrations().iter().any(|m| &m.version == *v)) {
+                println!("{:<8} {}", "unknown", version);
+            }
+        },
+        Command::Up => {
+            let applied = migrator.run(&conn).await?;
+            if applied.is_empty() {
+                println!("No pending migration");
+            }
+            for version in applied {
+                println!("Applied {}", version);
+            }
+        },
+        Command::Down => match migrator.revert(&conn).await? {
+            Some(version) => println!("Reverted {}", version),
+            None => println!("No applied migration")
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
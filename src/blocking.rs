@@ -0,0 +1,198 @@
+//! Blocking client, with the `blocking` feature
+//!
+//! Each connection drives an internal tokio runtime, so it can be used from code that is not async.
+//! Methods must not be called from within an async runtime.
+//! ```no_run
+//! # use rqlite::*;
+//! # type Error = Box<dyn std::error::Error + Send + Sync>;
+//! # async fn run() -> Result<(), Error> {
+//! use rqlite::ConnectOptions;
+//! use rqlite::blocking::Connection;
+//!
+//! let conn = Connection::connect(&ConnectOptions::new("127.0.0.1", 4001))?;
+//! conn.execute("INSERT INTO foo(name) VALUES (?)", par!("fiona"))?;
+//! let rows = conn.fetch_all("SELECT * FROM foo", par!())?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::Arc;
+use std::time::Duration;
+use serde::de::DeserializeOwned;
+use tokio::runtime::Runtime;
+use crate::connect::{ConnectOptions, Node};
+use crate::cursor::{Cursor, Rows, StatementError, StatementResult};
+use crate::error::RqliteError;
+use crate::options::QueryOptions;
+use crate::row::Row;
+use crate::schema::{Column, Index, Table};
+use crate::types::IntoParams;
+
+/// Blocking rqlite connection
+///
+/// Clones share the runtime and the socket.
+#[derive(Clone, Debug)]
+pub struct Connection {
+    inner: crate::connect::Connection,
+    runtime: Arc<Runtime>
+}
+
+impl Connection {
+    /// Establish connection to rqlite node
+    ///
+    /// Returns RqliteError on unsuccessful connection, error creating Tls context or the runtime
+    pub fn connect(options: &ConnectOptions) -> Result<Connection, RqliteError> {
+        // a worker thread keeps connections alive between calls
+        let runtime = match tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .enable_all()
+                .build() {
+            Ok(v) => v,
+            Err(e) => return Err(RqliteError::Io(e))
+        };
+        let inner = runtime.block_on(options.clone().connect())?;
+        Ok(Connection { inner, runtime: Arc::new(runtime) })
+    }
+
+    /// Async connection used by this connection
+    pub fn async_connection(&self) -> &crate::connect::Connection {
+        &self.inner
+    }
+
+    /// See [`Connection::execute()`](crate::Connection::execute)
+    pub fn execute(&self, query: &str, params: impl IntoParams) -> Result<Cursor<'_>, RqliteError> {
+        self.runtime.block_on(self.inner.execute(query, params))
+    }
+
+    /// See [`Connection::query()`](crate::Connection::query)
+    pub fn query(&self, query: &str, params: impl IntoParams) -> Result<Cursor<'_>, RqliteError> {
+        self.runtime.block_on(self.inner.query(query, params))
+    }
+
+    /// See [`Connection::query_with()`](crate::Connection::query_with)
+    pub fn query_with(&self, query: &str, params: impl IntoParams, options: &QueryOptions) -> Result<Cursor<'_>, RqliteError> {
+        self.runtime.block_on(self.inner.query_with(query, params, options))
+    }
+
+    /// See [`Connection::query_as()`](crate::Connection::query_as)
+    pub fn query_as<T: DeserializeOwned>(&self, query: &str, params: impl IntoParams) -> Result<Vec<T>, RqliteError> {
+        self.runtime.block_on(self.inner.query_as(query, params))
+    }
+
+    /// See [`Connection::query_batch()`](crate::Connection::query_batch)
+    pub fn query_batch(&self, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<Cursor<'_>, RqliteError> {
+        self.runtime.block_on(self.inner.query_batch(queries))
+    }
+
+    /// See [`Connection::query_rows()`](crate::Connection::query_rows)
+    pub fn query_rows(&self, query: &str, params: impl IntoParams) -> Result<Rows, RqliteError> {
+        self.runtime.block_on(self.inner.query_rows(query, params))
+    }
+
+    /// See [`Connection::fetch_all()`](crate::Connection::fetch_all)
+    pub fn fetch_all(&self, query: &str, params: impl IntoParams) -> Result<Vec<Row>, RqliteError> {
+        self.runtime.block_on(self.inner.fetch_all(query, params))
+    }
+
+    /// See [`Connection::fetch_one()`](crate::Connection::fetch_one)
+    pub fn fetch_one(&self, query: &str, params: impl IntoParams) -> Result<Row, RqliteError> {
+        self.runtime.block_on(self.inner.fetch_one(query, params))
+    }
+
+    /// See [`Connection::fetch_optional()`](crate::Connection::fetch_optional)
+    pub fn fetch_optional(&self, query: &str, params: impl IntoParams) -> Result<Option<Row>, RqliteError> {
+        self.runtime.block_on(self.inner.fetch_optional(query, params))
+    }
+
+    /// See [`Connection::query_scalar()`](crate::Connection::query_scalar)
+    pub fn query_scalar<T: DeserializeOwned>(&self, query: &str, params: impl IntoParams) -> Result<T, RqliteError> {
+        self.runtime.block_on(self.inner.query_scalar(query, params))
+    }
+
+    /// See [`Connection::execute_named()`](crate::Connection::execute_named)
+    pub fn execute_named(&self, query: &str, params: serde_json::Map<String, serde_json::Value>) -> Result<Cursor<'_>, RqliteError> {
+        self.runtime.block_on(self.inner.execute_named(query, params))
+    }
+
+    /// See [`Connection::query_named()`](crate::Connection::query_named)
+    pub fn query_named(&self, query: &str, params: serde_json::Map<String, serde_json::Value>) -> Result<Cursor<'_>, RqliteError> {
+        self.runtime.block_on(self.inner.query_named(query, params))
+    }
+
+    /// See [`Connection::transaction()`](crate::Connection::transaction)
+    pub fn transaction(&self, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<Vec<StatementResult>, RqliteError> {
+        self.runtime.block_on(self.inner.transaction(queries))
+    }
+
+    /// See [`Connection::execute_batch()`](crate::Connection::execute_batch)
+    pub fn execute_batch(&self, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<Vec<Result<StatementResult, StatementError>>, RqliteError> {
+        self.runtime.block_on(self.inner.execute_batch(queries))
+    }
+
+    /// See [`Connection::execute_many()`](crate::Connection::execute_many)
+    pub fn execute_many(&self, query: &str, params: Vec<impl IntoParams>) -> Result<Cursor<'_>, RqliteError> {
+        self.runtime.block_on(self.inner.execute_many(query, params))
+    }
+
+    /// See [`Connection::tables()`](crate::Connection::tables)
+    pub fn tables(&self) -> Result<Vec<Table>, RqliteError> {
+        self.runtime.block_on(self.inner.tables())
+    }
+
+    /// See [`Connection::columns()`](crate::Connection::columns)
+    pub fn columns(&self, table: &str) -> Result<Vec<Column>, RqliteError> {
+        self.runtime.block_on(self.inner.columns(table))
+    }
+
+    /// See [`Connection::indexes()`](crate::Connection::indexes)
+    pub fn indexes(&self, table: &str) -> Result<Vec<Index>, RqliteError> {
+        self.runtime.block_on(self.inner.indexes(table))
+    }
+
+    /// See [`Connection::nodes()`](crate::Connection::nodes)
+    pub fn nodes(&self, show_nonvoters: bool) -> Result<Vec<Node>, RqliteError> {
+        self.runtime.block_on(self.inner.nodes(show_nonvoters))
+    }
+
+    /// See [`Connection::leader()`](crate::Connection::leader)
+    pub fn leader(&self) -> Result<Node, RqliteError> {
+        self.runtime.block_on(self.inner.leader())
+    }
+
+    /// See [`Connection::ready()`](crate::Connection::ready)
+    pub fn ready(&self) -> Result<bool, RqliteError> {
+        self.runtime.block_on(self.inner.ready())
+    }
+
+    /// See [`Connection::ping()`](crate::Connection::ping)
+    pub fn ping(&self) -> Result<Duration, RqliteError> {
+        self.runtime.block_on(self.inner.ping())
+    }
+
+    /// See [`Connection::is_leader()`](crate::Connection::is_leader)
+    pub fn is_leader(&self) -> Result<bool, RqliteError> {
+        self.runtime.block_on(self.inner.is_leader())
+    }
+
+    /// Write a backup of the database to `file`, returns the number of bytes written
+    ///
+    /// See [`Connection::backup()`](crate::Connection::backup)
+    pub fn backup(&self, file: std::fs::File) -> Result<u64, RqliteError> {
+        self.runtime.block_on(async {
+            self.inner.backup(tokio::fs::File::from_std(file)).await
+        })
+    }
+
+    /// Replace the database with the SQLite database `file`
+    ///
+    /// See [`Connection::restore_from_sqlite()`](crate::Connection::restore_from_sqlite)
+    pub fn restore_from_sqlite(&self, file: std::fs::File) -> Result<(), RqliteError> {
+        self.runtime.block_on(async {
+            self.inner.restore_from_sqlite(tokio::fs::File::from_std(file)).await
+        })
+    }
+
+    /// See [`Connection::remove()`](crate::Connection::remove)
+    pub fn remove(&self, id: &str) -> Result<(), RqliteError> {
+        self.runtime.block_on(self.inner.remove(id))
+    }
+}
@@ -0,0 +1,139 @@
+use serde::de::DeserializeOwned;
+use crate::connect::ConnectOptions;
+use crate::cursor::{StatementError, StatementResult};
+use crate::error::RqliteError;
+use crate::options::QueryOptions;
+use crate::pool::{Pool, PoolConnection, PoolOptions};
+use crate::row::Row;
+use crate::types::IntoParams;
+
+/// Cheap to clone rqlite client
+///
+/// Owns a [`Pool`] of connections, every clone shares it. Methods return owned results
+/// so a client can be stored in application state and cloned into request handlers.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// use rqlite::{Client, ConnectOptions};
+///
+/// let client = Client::connect(&ConnectOptions::new("127.0.0.1", 4001)).await?;
+/// let handler_client = client.clone();
+/// tokio::spawn(async move {
+///     handler_client.execute("INSERT INTO foo(name) VALUES (?)", par!("fiona")).await
+/// });
+/// let rows = client.fetch_all("SELECT * FROM foo", par!()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Client {
+    pool: Pool
+}
+
+impl Client {
+    /// Create a client using a pool with default [`PoolOptions`]
+    ///
+    /// Returns RqliteError on unsuccessful connection or error creating Tls context
+    pub async fn connect(options: &ConnectOptions) -> Result<Client, RqliteError> {
+        Ok(Client::with_pool(PoolOptions::new(options).connect().await?))
+    }
+
+    /// Create a client using connections of `pool`
+    pub fn with_pool(pool: Pool) -> Client {
+        Client { pool }
+    }
+
+    /// Pool used by the client
+    pub fn pool(&self) -> &Pool {
+        &self.pool
+    }
+
+    /// Borrow a connection, to use cursors or methods not available on the client
+    ///
+    /// Returns RqliteError if no connection could be opened
+    pub async fn connection(&self) -> Result<PoolConnection, RqliteError> {
+        self.pool.acquire().await
+    }
+
+    /// Execute a sql write statement
+    ///
+    /// See [`Connection::execute()`](crate::Connection::execute)
+    pub async fn execute(&self, query: &str, params: impl IntoParams) -> Result<StatementResult, RqliteError> {
+        let conn = self.connection().await?;
+        let cur = conn.execute(query, params).await?;
+        Ok(StatementResult {
+            rows_affected: cur.rows_affected().max(0) as usize,
+            last_insert_id: cur.last_row_id()
+        })
+    }
+
+    /// Execute a sql read query and get all rows
+    ///
+    /// See [`Connection::fetch_all()`](crate::Connection::fetch_all)
+    pub async fn fetch_all(&self, query: &str, params: impl IntoParams) -> Result<Vec<Row>, RqliteError> {
+        self.connection().await?.fetch_all(query, params).await
+    }
+
+    /// Execute a sql read query returning exactly one row
+    ///
+    /// See [`Connection::fetch_one()`](crate::Connection::fetch_one)
+    pub async fn fetch_one(&self, query: &str, params: impl IntoParams) -> Result<Row, RqliteError> {
+        self.connection().await?.fetch_one(query, params).await
+    }
+
+    /// Execute a sql read query returning at most one row
+    ///
+    /// See [`Connection::fetch_optional()`](crate::Connection::fetch_optional)
+    pub async fn fetch_optional(&self, query: &str, params: impl IntoParams) -> Result<Option<Row>, RqliteError> {
+        self.connection().await?.fetch_optional(query, params).await
+    }
+
+    /// Execute a sql read query with options and get all rows
+    ///
+    /// See [`Connection::query_with()`](crate::Connection::query_with)
+    pub async fn fetch_all_with(&self, query: &str, params: impl IntoParams, options: &QueryOptions) -> Result<Vec<Row>, RqliteError> {
+        let conn = self.connection().await?;
+        let mut cur = conn.query_with(query, params, options).await?;
+        Ok(cur.fetch_all())
+    }
+
+    /// Execute a sql read query and deserialize all rows into `T`
+    ///
+    /// See [`Connection::query_as()`](crate::Connection::query_as)
+    pub async fn query_as<T: DeserializeOwned>(&self, query: &str, params: impl IntoParams) -> Result<Vec<T>, RqliteError> {
+        self.connection().await?.query_as(query, params).await
+    }
+
+    /// Execute a sql read query returning a single value
+    ///
+    /// See [`Connection::query_scalar()`](crate::Connection::query_scalar)
+    pub async fn query_scalar<T: DeserializeOwned>(&self, query: &str, params: impl IntoParams) -> Result<T, RqliteError> {
+        self.connection().await?.query_scalar(query, params).await
+    }
+
+    /// Execute multiple statements atomically
+    ///
+    /// See [`Connection::transaction()`](crate::Connection::transaction)
+    pub async fn transaction(&self, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<Vec<StatementResult>, RqliteError> {
+        self.connection().await?.transaction(queries).await
+    }
+
+    /// Execute multiple statements, reporting errors per statement
+    ///
+    /// See [`Connection::execute_batch()`](crate::Connection::execute_batch)
+    pub async fn execute_batch(&self, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<Vec<Result<StatementResult, StatementError>>, RqliteError> {
+        self.connection().await?.execute_batch(queries).await
+    }
+}
+
+// Client and its futures are meant to be shared across tasks and threads
+#[allow(dead_code)]
+fn assert_send_sync(client: &Client) {
+    fn check<T: Send + Sync>() {}
+    fn check_future<F: std::future::Future + Send>(_: F) {}
+    check::<Client>();
+    check_future(client.execute("", Vec::new()));
+    check_future(client.fetch_all("", Vec::new()));
+    check_future(client.transaction(&[]));
+}
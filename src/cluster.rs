@@ -0,0 +1,358 @@
+use std::sync::Arc;
+use hyper::Uri;
+use crate::auth::Auth;
+use crate::connect::{ConnectOptions, Connection, Node};
+use crate::cursor::{Cursor, StatementResult};
+use crate::error::RqliteError;
+use crate::options::{Level, QueryOptions};
+use crate::retry::ErrorClass;
+use crate::types::{into_params, IntoParams};
+
+/// Cluster aware connection
+///
+/// Requests are sent to the current leader of the cluster.
+/// The leader is discovered using `/nodes`, and discovered again
+/// when the connection to it is lost or it steps down.
+#[derive(Debug)]
+pub struct ClusterConnection {
+    /// Options of the seed node, used as template for other nodes
+    options: ConnectOptions,
+    /// Credentials, shared by the connections to every node
+    auth: Arc<Auth>,
+    /// Known nodes of the cluster
+    nodes: Vec<ConnectOptions>,
+    /// Connection to the leader
+    leader: Option<Connection>,
+    /// Followers reads are balanced across
+    followers: Vec<Follower>,
+    /// Follower serving the next read
+    next_follower: usize,
+    /// Send reads to followers
+    stale_reads: bool
+}
+
+#[derive(Debug)]
+struct Follower {
+    options: ConnectOptions,
+    auth: Arc<Auth>,
+    connection: Option<Connection>
+}
+
+impl Follower {
+    /// Make sure follower is connected, returns false if it is unreachable
+    async fn connect(&mut self) -> bool {
+        if let Some(conn) = self.connection.as_mut() {
+            if !conn.is_closed().await {
+                return true;
+            }
+        }
+        self.connection = self.options.connect_with_auth(self.auth.clone()).await.ok();
+        self.connection.is_some()
+    }
+}
+
+impl ConnectOptions {
+    /// Establish cluster aware connection, starting from this node
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let mut conn = ConnectOptions::new("my.node.local", 4001)
+    ///        .user("root")
+    ///        .pass("root")
+    ///        .connect_cluster().await?;
+    /// conn.execute("INSERT INTO foo(name) VALUES (?)", par!("fiona")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError if no leader could be found
+    pub async fn connect_cluster(&mut self) -> Result<ClusterConnection, RqliteError> {
+        let mut cluster = ClusterConnection {
+            options: self.clone(),
+            auth: Arc::new(self.auth()),
+            nodes: self.seeds(),
+            leader: None,
+            followers: Vec::new(),
+            next_follower: 0,
+            stale_reads: false
+        };
+        cluster.discover().await?;
+        Ok(cluster)
+    }
+}
+
+/// Cluster aware rqlite connection
+impl ClusterConnection {
+    /// Get connection to the leader
+    ///
+    /// Leader is discovered again if the connection to it was lost.
+    ///
+    /// Returns RqliteError if no leader could be found
+    pub async fn connection(&mut self) -> Result<&Connection, RqliteError> {
+        let lost = match self.leader.as_ref() {
+            Some(leader) => leader.is_closed().await,
+            None => true
+        };
+        if lost {
+            self.discover().await?;
+        }
+        match self.leader.as_ref() {
+            Some(leader) => Ok(leader),
+            None => Err(RqliteError::NoLeader)
+        }
+    }
+
+    /// Balance read queries across followers (default false)
+    ///
+    /// Reads done with [`ClusterConnection::query()`] use the `none` consistency level,
+    /// followers answer them from their local database without checking with the leader
+    /// so results can be slightly stale. When no follower is reachable reads go to the leader.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let mut conn = ConnectOptions::nodes(&[("node1.local", 4001), ("node2.local", 4001)])?
+    ///        .connect_cluster().await?;
+    /// conn.stale_reads(true);
+    /// let cur = conn.query("SELECT * FROM foo", par!()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stale_reads(&mut self, enable: bool) -> &mut ClusterConnection {
+        self.stale_reads = enable;
+        self
+    }
+
+    /// Execute a sql write statement on the leader
+    ///
+    /// See [`Connection::execute()`]
+    pub async fn execute(&mut self, query: &str, params: impl IntoParams) -> Result<Cursor<'_>, RqliteError> {
+        let request = LeaderRequest::Execute(query, into_params(params)?);
+        self.leader_cursor(&request).await
+    }
+
+    /// Execute a sql read query
+    ///
+    /// Sent to a follower if [`ClusterConnection::stale_reads()`] is set, to the leader otherwise.
+    ///
+    /// See [`Connection::query()`]
+    pub async fn query(&mut self, query: &str, params: impl IntoParams) -> Result<Cursor<'_>, RqliteError> {
+        if self.stale_reads {
+            return self.query_with(query, params, QueryOptions::new().level(Level::None)).await;
+        }
+        let options = QueryOptions::new();
+        let request = LeaderRequest::Query(query, into_params(params)?, &options);
+        self.leader_cursor(&request).await
+    }
+
+    /// Execute read query with options
+    ///
+    /// Reads with the `none` consistency level are balanced across followers,
+    /// other levels need the leader and are sent to it.
+    ///
+    /// See [`Connection::query_with()`]
+    pub async fn query_with(&mut self, query: &str, params: impl IntoParams, options: &QueryOptions) -> Result<Cursor<'_>, RqliteError> {
+        if options.level == Some(Level::None) {
+            if let Some(i) = self.follower().await {
+                // follower() only returns connected followers
+                return self.followers[i].connection.as_ref().unwrap().query_with(query, params, options).await;
+            }
+        }
+        let request = LeaderRequest::Query(query, into_params(params)?, options);
+        self.leader_cursor(&request).await
+    }
+
+    /// Send `request` to the leader, if the connection fails or the node lost
+    /// its leadership the leader is discovered again and the request retried once
+    async fn leader_cursor(&mut self, request: &LeaderRequest<'_>) -> Result<Cursor<'_>, RqliteError> {
+        // the first attempt uses a clone, so the leader can be replaced when it fails
+        let leader = self.connection().await?.clone();
+        let mut cursor = leader.cursor();
+        match request.send(&mut cursor).await {
+            Ok(()) => {
+                // connection() succeeded, so the leader is set
+                let leader = self.leader.as_ref().unwrap();
+                return Ok(cursor.rebind(leader));
+            },
+            Err(e) if !rediscover(&e) => return Err(e),
+            Err(_) => ()
+        }
+        self.discover().await?;
+        let mut cursor = self.connection().await?.cursor();
+        request.send(&mut cursor).await?;
+        Ok(cursor)
+    }
+
+    /// Index of the next reachable follower, round robin
+    async fn follower(&mut self) -> Option<usize> {
+        for _ in 0..self.followers.len() {
+            let i = self.next_follower % self.followers.len();
+            self.next_follower = self.next_follower.wrapping_add(1);
+            if self.followers[i].connect().await {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Execute multiple statements atomically on the leader
+    ///
+    /// See [`Connection::transaction()`]
+    pub async fn transaction(&mut self, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<Vec<StatementResult>, RqliteError> {
+        match self.connection().await?.transaction(queries).await {
+            Err(e) if rediscover(&e) => {
+                self.discover().await?;
+                self.connection().await?.transaction(queries).await
+            },
+            result => result
+        }
+    }
+
+    /// List all node in cluster
+    ///
+    /// See [`Connection::nodes()`]
+    pub async fn nodes(&mut self, show_nonvoters: bool) -> Result<Vec<Node>, RqliteError> {
+        match self.connection().await?.nodes(show_nonvoters).await {
+            Err(e) if rediscover(&e) => {
+                self.discover().await?;
+                self.connection().await?.nodes(show_nonvoters).await
+            },
+            result => result
+        }
+    }
+
+    /// Find the leader by asking every known node, and connect to it
+    async fn discover(&mut self) -> Result<(), RqliteError> {
+        self.leader = None;
+        let mut last_error = None;
+        for node in self.nodes.clone() {
+            let conn = match node.connect_with_auth(self.auth.clone()).await {
+                Ok(v) => v,
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            let nodes = match conn.nodes(false).await {
+                Ok(v) => v,
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            self.nodes = nodes.iter()
+                    .filter_map(|n| self.node_options(&n.api_addr))
+                    .collect();
+            for seed in self.options.seeds() {
+                if !self.nodes.iter().any(|n| n.same_node(&seed)) {
+                    self.nodes.push(seed);
+                }
+            }
+
+            let leader = match nodes.iter().find(|n| n.leader).and_then(|n| self.node_options(&n.api_addr)) {
+                Some(v) => v,
+                None => {
+                    last_error = Some(RqliteError::NoLeader);
+                    continue;
+                }
+            };
+            self.followers = nodes.iter()
+                    .filter(|n| !n.leader && n.reachable)
+                    .filter_map(|n| self.node_options(&n.api_addr))
+                    .map(|options| Follower { options, auth: self.auth.clone(), connection: None })
+                    .collect();
+            if leader.same_node(&node) {
+                self.leader = Some(conn);
+                return Ok(());
+            }
+            match leader.connect_with_auth(self.auth.clone()).await {
+                Ok(v) => {
+                    self.leader = Some(v);
+                    return Ok(());
+                },
+                Err(e) => last_error = Some(e)
+            }
+        }
+        Err(last_error.unwrap_or_else(|| RqliteError::Connection("No leader found".to_owned())))
+    }
+
+    /// Connect options for a node, from its api address
+    fn node_options(&self, api_addr: &str) -> Option<ConnectOptions> {
+        self.options.for_node(&api_addr.parse::<Uri>().ok()?)
+    }
+}
+
+/// Request sent to the leader by [`ClusterConnection::leader_cursor()`]
+enum LeaderRequest<'q> {
+    Execute(&'q str, Vec<serde_json::Value>),
+    Query(&'q str, Vec<serde_json::Value>, &'q QueryOptions)
+}
+
+impl LeaderRequest<'_> {
+    async fn send(&self, cursor: &mut Cursor<'_>) -> Result<(), RqliteError> {
+        match self {
+            LeaderRequest::Execute(query, params) => cursor.execute(query, params.clone()).await,
+            LeaderRequest::Query(query, params, options) => cursor.query_with(query, params.clone(), options).await
+        }
+    }
+}
+
+/// Check if `error` means the leader is gone, so it must be discovered again
+fn rediscover(error: &RqliteError) -> bool {
+    matches!(ErrorClass::of(error), Some(ErrorClass::Connection | ErrorClass::Leadership))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use super::*;
+
+    /// Mock node, `leader` gives the port of the node reported as leader by `/nodes`
+    fn serve(leader: Arc<dyn Fn() -> u16 + Send + Sync>, execute: &'static str, executed: Arc<AtomicBool>) -> u16 {
+        let make = make_service_fn(move |_| {
+            let (leader, executed) = (leader.clone(), executed.clone());
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let body = if req.uri().path() == "/nodes" {
+                        format!(r#"{{"nodes": [{{"id": "{0}", "api_addr": "http://127.0.0.1:{0}", "addr": "127.0.0.1:1",
+                            "voter": true, "reachable": true, "leader": true}}]}}"#, leader())
+                    } else {
+                        executed.store(true, Ordering::SeqCst);
+                        execute.to_owned()
+                    };
+                    async move { Ok::<_, Infallible>(Response::new(Body::from(body))) }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make);
+        let port = server.local_addr().port();
+        tokio::spawn(server);
+        port
+    }
+
+    #[tokio::test]
+    async fn leader_is_discovered_again_when_it_steps_down() {
+        let new_leader = Arc::new(AtomicU16::new(0));
+        let stepped_down = Arc::new(AtomicBool::new(false));
+        let port = new_leader.clone();
+        let executed = Arc::new(AtomicBool::new(false));
+        let new_port = serve(Arc::new(move || port.load(Ordering::SeqCst)), r#"{"results": [{"last_insert_id": 1, "rows_affected": 1}]}"#, executed.clone());
+        new_leader.store(new_port, Ordering::SeqCst);
+
+        let old_port = Arc::new(AtomicU16::new(0));
+        let (old, new, down) = (old_port.clone(), new_leader.clone(), stepped_down.clone());
+        let leader = move || if down.load(Ordering::SeqCst) { new.load(Ordering::SeqCst) } else { old.load(Ordering::SeqCst) };
+        let seed = serve(Arc::new(leader), r#"{"results": [{"error": "not leader"}]}"#, stepped_down.clone());
+        old_port.store(seed, Ordering::SeqCst);
+
+        let mut conn = ConnectOptions::new("127.0.0.1", seed).connect_cluster().await.unwrap();
+        let cur = conn.execute("INSERT INTO foo(name) VALUES (?)", crate::par!("fiona")).await.unwrap();
+        assert_eq!(cur.rows_affected(), 1);
+        assert!(stepped_down.load(Ordering::SeqCst) && executed.load(Ordering::SeqCst));
+    }
+}
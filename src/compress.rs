@@ -0,0 +1,98 @@
+//! Gzip compression of http bodies, with the `gzip` feature
+//!
+//! Responses are requested gzipped and decompressed as they arrive,
+//! before middlewares and the size limit see them. Request bodies are gzipped
+//! from the size set with [`ConnectOptions::compress_requests()`](crate::ConnectOptions::compress_requests).
+#![cfg(feature = "gzip")]
+use std::io::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::write::{GzDecoder, GzEncoder};
+use futures_core::Stream;
+use hyper::{Body, Request, Response};
+use hyper::http::request::Builder;
+use hyper::header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH};
+use crate::error::RqliteError;
+
+/// Gzip `body` if it is at least `threshold` bytes
+pub(crate) fn encode(req_builder: Builder, body: Option<Bytes>, threshold: Option<usize>) -> Result<(Builder, Option<Bytes>), RqliteError> {
+    let body = match (body, threshold) {
+        (Some(body), Some(threshold)) if body.len() >= threshold => body,
+        (body, _) => return Ok((req_builder, body))
+    };
+    let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), Compression::fast());
+    let compressed = match encoder.write_all(&body).and_then(|_| encoder.finish()) {
+        Ok(v) => v,
+        Err(e) => return Err(RqliteError::Io(e))
+    };
+    Ok((req_builder.header(CONTENT_ENCODING, "gzip"), Some(compressed.into())))
+}
+
+/// Ask for a gzipped response, unless the request already sets an encoding
+pub(crate) fn accept_gzip(req: &mut Request<Body>) {
+    if !req.headers().contains_key(ACCEPT_ENCODING) {
+        req.headers_mut().insert(ACCEPT_ENCODING, HeaderValue::from_static("gzip"));
+    }
+}
+
+/// Decompress `resp` if it is gzipped
+pub(crate) fn decode(resp: Response<Body>) -> Response<Body> {
+    let gzipped = resp.headers().get(CONTENT_ENCODING)
+            .is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"gzip"));
+    if !gzipped {
+        return resp;
+    }
+    let (mut parts, body) = resp.into_parts();
+    parts.headers.remove(CONTENT_ENCODING);
+    parts.headers.remove(CONTENT_LENGTH);
+    let body = Body::wrap_stream(Gunzip { body, decoder: Some(GzDecoder::new(Vec::new())) });
+    Response::from_parts(parts, body)
+}
+
+/// Body decompressed chunk by chunk
+struct Gunzip {
+    body: Body,
+    /// None once the body ended
+    decoder: Option<GzDecoder<Vec<u8>>>
+}
+
+impl Stream for Gunzip {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let decoder = match this.decoder.as_mut() {
+                Some(v) => v,
+                None => return Poll::Ready(None)
+            };
+            match Pin::new(&mut this.body).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    if let Err(e) = decoder.write_all(&chunk) {
+                        this.decoder = None;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    let out = std::mem::take(decoder.get_mut());
+                    if !out.is_empty() {
+                        return Poll::Ready(Some(Ok(out.into())));
+                    }
+                },
+                Poll::Ready(Some(Err(e))) => {
+                    this.decoder = None;
+                    return Poll::Ready(Some(Err(std::io::Error::new(std::io::ErrorKind::Other, e))));
+                },
+                Poll::Ready(None) => {
+                    let decoder = this.decoder.take();
+                    return match decoder.map(|d| d.finish()) {
+                        Some(Ok(out)) if !out.is_empty() => Poll::Ready(Some(Ok(out.into()))),
+                        Some(Err(e)) => Poll::Ready(Some(Err(e))),
+                        _ => Poll::Ready(None)
+                    };
+                },
+                Poll::Pending => return Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Serde helpers for configuration types
+use std::time::Duration;
+use serde::{Deserialize, Deserializer, Serializer, de};
+use crate::connect::ConnectOptions;
+use crate::proxy::unbracket;
+use crate::url::parse_duration;
+
+/// Options used for fields missing from a deserialized [`ConnectOptions`]
+pub(crate) fn default_options() -> ConnectOptions {
+    ConnectOptions::new("localhost", 4001)
+}
+
+/// Host name, ipv6 addresses may be enclosed in brackets
+pub(crate) fn host<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let host = String::deserialize(deserializer)?;
+    Ok(unbracket(&host).to_owned())
+}
+
+/// `[host, port]` list, ipv6 addresses may be enclosed in brackets
+pub(crate) fn nodes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(String, u16)>, D::Error> {
+    let nodes = Vec::<(String, u16)>::deserialize(deserializer)?;
+    Ok(nodes.into_iter().map(|(host, port)| (unbracket(&host).to_owned(), port)).collect())
+}
+
+/// Duration written as a string like `5s` or `500ms`, or as a number of seconds
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDuration {
+    Secs(u64),
+    Text(String)
+}
+
+impl RawDuration {
+    fn into_duration<E: de::Error>(self) -> Result<Duration, E> {
+        match self {
+            RawDuration::Secs(secs) => Ok(Duration::from_secs(secs)),
+            RawDuration::Text(text) => parse_duration(&text).map_err(|e| E::custom(e.to_string()))
+        }
+    }
+}
+
+fn format_duration(duration: &Duration) -> String {
+    let millis = duration.as_millis();
+    if millis % 1000 == 0 {
+        format!("{}s", millis / 1000)
+    } else {
+        format!("{}ms", millis)
+    }
+}
+
+pub(crate) mod duration {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_duration(duration))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        RawDuration::deserialize(deserializer)?.into_duration()
+    }
+}
+
+pub(crate) mod opt_duration {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&format_duration(duration)),
+            None => serializer.serialize_none()
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Option::<RawDuration>::deserialize(deserializer)?
+                .map(RawDuration::into_duration)
+                .transpose()
+    }
+}
+
+/// PEM data written as a string
+pub(crate) mod pem {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(pem: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf8_lossy(pem))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        Ok(String::deserialize(deserializer)?.into_bytes())
+    }
+}
+
+/// Proxy url, with the credentials of its userinfo redacted when serialized
+pub(crate) fn redacted_proxy<S: Serializer>(url: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match url {
+        Some(url) => serializer.serialize_some(&redact_userinfo(url)),
+        None => serializer.serialize_none()
+    }
+}
+
+fn redact_userinfo(url: &str) -> String {
+    let start = url.find("://").map_or(0, |i| i + 3);
+    let authority = &url[start..];
+    let end = authority.find(['/', '?', '#']).unwrap_or(authority.len());
+    match authority[..end].rfind('@') {
+        Some(at) => format!("{}***{}", &url[..start], &authority[at..]),
+        None => url.to_owned()
+    }
+}
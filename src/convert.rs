@@ -0,0 +1,51 @@
+//! Parameters and columns of types from optional dependencies
+//!
+//! With the `uuid` feature a `Uuid` is sent as hyphenated text, pass `uuid.as_bytes()`
+//! to store it as a 16 byte blob instead. Columns are read from both.
+//!
+//! With the `decimal` feature a `Decimal` is sent as text, store it in a `TEXT` column:
+//! columns with numeric affinity would convert it to a float.
+use serde_json::Value;
+#[cfg(any(feature = "chrono", feature = "time"))]
+use crate::datetime;
+#[cfg(any(feature = "uuid", feature = "decimal"))]
+use crate::types::ToParam;
+
+#[cfg(feature = "uuid")]
+impl ToParam for uuid::Uuid {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::String(self.hyphenated().to_string()))
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl ToParam for rust_decimal::Decimal {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::String(self.to_string()))
+    }
+}
+
+/// Other representations of a column value, tried by [`Row::get()`](crate::Row::get)
+/// when the value cannot be read directly
+///
+/// `blob` holds the decoded bytes of blob columns, arguments are unused without features.
+#[allow(unused_mut, unused_variables)]
+pub(crate) fn alternatives(value: &Value, blob: Option<&[u8]>) -> Vec<Value> {
+    let mut values = Vec::new();
+    #[cfg(feature = "chrono")]
+    values.extend(datetime::chrono_alternatives(value));
+    #[cfg(feature = "time")]
+    values.extend(datetime::time_alternatives(value));
+    #[cfg(feature = "uuid")]
+    values.extend(blob.and_then(|bytes| uuid::Uuid::from_slice(bytes).ok()).map(|v| Value::String(v.to_string())));
+    #[cfg(feature = "decimal")]
+    values.extend(decimal_alternative(value));
+    values
+}
+
+/// Text in scientific notation, like `1.5e3`
+#[cfg(feature = "decimal")]
+fn decimal_alternative(value: &Value) -> Option<Value> {
+    let s = value.as_str().filter(|s| s.contains(['e', 'E']))?;
+    rust_decimal::Decimal::from_scientific(s).ok().map(|v| Value::String(v.to_string()))
+}
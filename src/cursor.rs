@@ -1,22 +1,41 @@
-use crate::connect::Connection;
+use crate::connect::{status_error, with_timeout, Connection};
 use hyper::Request;
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
 use serde_json::{self, json};
-use crate::types::{parse_vec_types, Type};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use futures_core::Stream;
+use std::time::{Duration, Instant};
+use crate::types::{into_params, ColumnTypes, IntoParams, Type};
 use crate::row::Row;
-use crate::error::RqliteError;
+use crate::stream::RowStream;
+use crate::error::{RqliteError, SqliteErrorKind};
+use crate::options::QueryOptions;
+use crate::retry::{is_leadership_error, ErrorClass};
+use crate::hooks;
+use crate::stats;
+use crate::trace;
 
 /// Cursor
 /// Holds all info when executing a command
 #[derive(Debug)]
 pub struct Cursor<'a> {
     /// Connection object
-    connection: &'a mut Connection,
+    connection: &'a Connection,
     //messages: Vec<String>,
     /// Last row in table
     lastrowid: Option<usize>,
     /// column and type
     description: Vec<(String, Type)>,
+    /// column names, shared with rows
+    columns: Arc<Vec<String>>,
+    /// column types
+    types: Arc<Vec<Type>>,
     /// Number of row read 
     rownumber: usize,
     /// Number of rows affected/got from query
@@ -24,120 +43,636 @@ pub struct Cursor<'a> {
     //arraysize: isize,
     /// Rows result
     rows: Vec<Vec<serde_json::Value>>,
+    /// Results of the next statements in a batch
+    pending: VecDeque<ResultMap>,
+    /// Execution time of the current result, reported with timings
+    timing: Option<Duration>,
+    /// Raft index of the last request, reported with raft_index
+    raft_index: Option<u64>,
     //_column_type_cache: Option<String>
 }
 
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize)]
 struct ResultMap {
     error: Option<String>,
     columns: Option<Vec<String>>, 
-    #[serde(default)]
-    #[serde(deserialize_with = "parse_vec_types")]
-    types: Option<Vec<Type>>,
+    types: Option<ColumnTypes>,
     values: Option<Vec<Vec<serde_json::Value>>>,
+    /// Rows of associative results
+    rows: Option<Vec<serde_json::Map<String, serde_json::Value>>>,
+    /// Execution time in seconds, with timings
+    time: Option<f64>,
     rows_affected: Option<usize>,
     last_insert_id: Option<usize>,
 }
 
 #[derive(Deserialize)]
 struct QueryResult {
-    results: Option<Vec<ResultMap>>
+    /// Error of the whole request, like `stale read`
+    error: Option<String>,
+    results: Option<Vec<ResultMap>>,
+    sequence_number: Option<u64>,
+    raft_index: Option<u64>
+}
+
+/// Result of a single statement executed in a batch or transaction
+#[derive(Clone, Debug)]
+pub struct StatementResult {
+    /// Number of rows affected by the statement
+    pub rows_affected: usize,
+    /// Id of the last inserted row, if any
+    pub last_insert_id: Option<usize>
+}
+
+/// Error of a single statement executed in a batch or transaction
+#[derive(Clone, Debug)]
+pub struct StatementError {
+    /// Position of the statement in the batch
+    pub index: usize,
+    /// Error reported by rqlite
+    pub message: String
+}
+
+impl StatementError {
+    /// Kind of the SQLite error
+    pub fn kind(&self) -> SqliteErrorKind {
+        SqliteErrorKind::from_message(&self.message)
+    }
+}
+
+impl Error for StatementError {}
+
+impl fmt::Display for StatementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "statement {} failed: {}", self.index, self.message)
+    }
+}
+
+/// Build the json representation of a statement
+///
+/// Parameterized statements are sent as an array with the query first
+fn statement(query: &str, params: Vec<serde_json::Value>) -> serde_json::Value {
+    if params.is_empty() {
+        return json!(query);
+    }
+    let mut vec = Vec::with_capacity(params.len() + 1);
+    vec.push(json!(query));
+    vec.extend(params);
+    serde_json::Value::Array(vec)
+}
+
+/// Build the json representation of a statement with named parameters
+///
+/// Named parameters are sent as an object following the query
+fn named_statement(query: &str, params: serde_json::Map<String, serde_json::Value>) -> serde_json::Value {
+    if params.is_empty() {
+        return json!(query);
+    }
+    json!([query, params])
+}
+
+/// RqliteError of a statement error reported by rqlite
+pub(crate) fn result_error(error: String) -> RqliteError {
+    if error.contains("stale read") {
+        return RqliteError::StaleRead;
+    }
+    RqliteError::SqlError(error)
 }
 
 impl<'l> Cursor<'l> {
     /// Create a new cursor
     /// Multiple cursor can be created for same connection
     /// Every query deletes last queries data
-    pub(crate) fn new<'a>(connection: &'a mut Connection) -> Cursor {
+    pub(crate) fn new(connection: &'l Connection) -> Cursor<'l> {
         Cursor {
             connection,
             //messages: Vec::new(),
             lastrowid: None,
             description: Vec::new(),
+            columns: Arc::new(Vec::new()),
+            types: Arc::new(Vec::new()),
             rownumber: 0,
             rowcount: -1,
             //arraysize: 1,
             rows: Vec::new(),
+            pending: VecDeque::new(),
+            timing: None,
+            raft_index: None,
             //_column_type_cache: None
         }
     }
 
-    /// Execute sql query
+    /// Move the results to a cursor of `connection`, a clone of the connection of this cursor
+    pub(crate) fn rebind<'m>(self, connection: &'m Connection) -> Cursor<'m> {
+        Cursor {
+            connection,
+            lastrowid: self.lastrowid,
+            description: self.description,
+            columns: self.columns,
+            types: self.types,
+            rownumber: self.rownumber,
+            rowcount: self.rowcount,
+            rows: self.rows,
+            pending: self.pending,
+            timing: self.timing,
+            raft_index: self.raft_index
+        }
+    }
+
+    /// Execute sql write statement (`/db/execute` endpoint)
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// .connect().await?;
+    /// let mut cur = conn.cursor();
+    /// cur.execute("INSERT INTO foo(name) VALUES (?)", par!("fiona")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError on error to handle exception explicitly
+    pub async fn execute(&mut self, query: &str, params: impl IntoParams) -> Result<(), RqliteError> {
+        self.execute_uri("/db/execute?transaction", statement(query, into_params(params)?), None).await
+    }
+
+    /// Execute sql read query (`/db/query` endpoint)
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// .connect().await?;
+    /// let mut cur = conn.cursor();
+    /// cur.query("SELECT * FROM foo", par!()).await?;
+    /// # Ok(())
+    /// # }
     /// ```
-    /// let mut conn = ConnectOptions::new("127.0.0.1", 4001)
+    ///
+    /// Returns RqliteError on error to handle exception explicitly
+    pub async fn query(&mut self, query: &str, params: impl IntoParams) -> Result<(), RqliteError> {
+        self.execute_uri(&self.query_uri(&QueryOptions::new()), statement(query, into_params(params)?), None).await
+    }
+
+    /// Execute read query with options
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
     /// .connect().await?;
-    /// let cur = conn.cursor();
-    /// cur.execute("SELECT * FROM foo", par!()).await?;
+    /// let mut cur = conn.cursor();
+    /// cur.query_with("SELECT * FROM foo", par!(), QueryOptions::new().level(Level::Strong)).await?;
+    /// # Ok(())
+    /// # }
     /// ```
     ///
     /// Returns RqliteError on error to handle exception explicitly
-    pub async fn execute(&mut self, query: &str, params: Vec<serde_json::Value>) -> Result<(), Box<RqliteError>> {
-        let mut query_json;
-        if params.is_empty() {
-            query_json = json!([query]);
-        } else {
-            query_json = json!([[query]]);
-            let v = query_json[0].as_array_mut().unwrap();
-            for elem in params {
-                v.push(elem);
-            }
+    pub async fn query_with(&mut self, query: &str, params: impl IntoParams, options: &QueryOptions) -> Result<(), RqliteError> {
+        self.execute_uri(&self.query_uri(options), statement(query, into_params(params)?), options.timeout).await
+    }
+
+    /// Send a sql read query and stream its rows as the response arrives
+    ///
+    /// Rows are always requested as arrays, the request is not retried.
+    pub(crate) async fn query_stream(&self, query: &str, params: impl IntoParams, options: &QueryOptions) -> Result<RowStream, RqliteError> {
+        let mut options = options.clone();
+        options.associative = false;
+        let query_json = json!([statement(query, into_params(params)?)]);
+        let req_builder = Request::builder().method("POST").uri(self.query_uri(&options));
+        let req_builder = self.connection.auth(self.connection.base_headers(req_builder));
+        let connection = self.connection;
+        let resp = with_timeout(options.timeout.or(connection.settings.request_timeout), async {
+            connection.request(req_builder, Some(&query_json)).await
+        }).await?;
+        if resp.status() != 200 {
+            let status = resp.status().as_u16();
+            return Err(status_error(status, &connection.read_body(resp).await?));
         }
-        let mut req_builder = Request::builder().method("POST")
-                .uri(if query.starts_with("SELECT ") || query.starts_with("PRAGMA ") { "/db/query" } else { "/db/execute?transaction" });
-        req_builder = self.connection.auth(self.connection.base_headers(req_builder));
-        let resp    = self.connection.request(req_builder, Some(&query_json)).await?;
-
-        let mut last_insert_id: Option<usize> = None;
-        let mut rows_affected: isize = -1;
-
-        let body                = self.connection.read_body(resp).await?;
-        let result: QueryResult = self.connection.body(&body).await?;
-        match result.results {
-            Some(res) => {
-                rows_affected = 0;
-                for item in res {
-                    if item.error.is_some() {
-                        return Err(Box::new(RqliteError::SqlError(item.error.unwrap())));
-                    }
-
-                    if item.rows_affected.is_some() {
-                        rows_affected += item.rows_affected.unwrap() as isize;
-                    }
-                    if item.last_insert_id.is_some() {
-                        last_insert_id = Some(item.last_insert_id.unwrap());
-                    }
-                    match item.columns {
-                        Some(fields) => {
-                            if item.types.is_some() {
-                                let types = item.types.unwrap();
-                                for i in 0..fields.len() {
-                                    self.description.push((fields[i].clone(), types[i]));
-                                }
-                                if item.values.is_some() {
-                                    self.rows = item.values.unwrap();
-                                }
-                            }
-                        },
-                        None => {
-                            if query.starts_with("INSERT ") {
-                                self.lastrowid = last_insert_id;
-                            }
-                        }
-                    }
-                }
+        Ok(RowStream::new(resp.into_body()))
+    }
+
+    /// Execute sql write statement with named parameters
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// .connect().await?;
+    /// let mut cur = conn.cursor();
+    /// cur.execute_named("INSERT INTO foo(name, age) VALUES (:name, :age)", named_par!{"name" => "fiona", "age" => 5}).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError on error to handle exception explicitly
+    pub async fn execute_named(&mut self, query: &str, params: serde_json::Map<String, serde_json::Value>) -> Result<(), RqliteError> {
+        self.execute_uri("/db/execute?transaction", named_statement(query, params), None).await
+    }
+
+    /// Execute sql read query with named parameters
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// .connect().await?;
+    /// let mut cur = conn.cursor();
+    /// cur.query_named("SELECT * FROM foo WHERE name = :name", named_par!{"name" => "fiona"}).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError on error to handle exception explicitly
+    pub async fn query_named(&mut self, query: &str, params: serde_json::Map<String, serde_json::Value>) -> Result<(), RqliteError> {
+        self.execute_uri(&self.query_uri(&QueryOptions::new()), named_statement(query, params), None).await
+    }
+
+    /// Execute a statement already in json as a write
+    pub(crate) async fn execute_prepared(&mut self, statement: serde_json::Value) -> Result<(), RqliteError> {
+        self.execute_uri("/db/execute?transaction", statement, None).await
+    }
+
+    /// Execute a statement already in json as a read
+    pub(crate) async fn query_prepared(&mut self, statement: serde_json::Value, options: &QueryOptions) -> Result<(), RqliteError> {
+        self.execute_uri(&self.query_uri(options), statement, options.timeout).await
+    }
+
+    /// Execute multiple sql read queries in a single request
+    ///
+    /// Every query has its own result, use [`Cursor::next_result()`] to move from one to the next.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// .connect().await?;
+    /// let mut cur = conn.cursor();
+    /// cur.query_batch(&[
+    ///     ("SELECT * FROM foo", par!()),
+    ///     ("SELECT * FROM bar WHERE id = ?", par!(1)),
+    /// ]).await?;
+    /// while let Some(result) = cur.next_result() {
+    ///     result?;
+    ///     while let Some(row) = cur.next_row() {
+    ///         println!("{:?}", row);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError if the request fails, errors of queries are returned by [`Cursor::next_result()`]
+    pub async fn query_batch(&mut self, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<(), RqliteError> {
+        let query_json = serde_json::Value::Array(queries.iter()
+                .map(|(query, params)| statement(query, params.clone()))
+                .collect());
+        let result = self.send(&self.query_uri(&QueryOptions::new()), &query_json).await?;
+
+        self.reset();
+        self.pending = result.results.unwrap_or_default().into();
+        Ok(())
+    }
+
+    /// Move to the result of the next statement of a batch
+    ///
+    /// Returns None when there are no more results,
+    /// or the RqliteError of the statement if it failed.
+    pub fn next_result(&mut self) -> Option<Result<(), RqliteError>> {
+        let item = self.pending.pop_front()?;
+        Some(self.load_result(item))
+    }
+
+    /// `/db/query` url for `options`, unset options use connection defaults
+    fn query_uri(&self, options: &QueryOptions) -> String {
+        let mut options = options.clone();
+        if options.level.is_none() {
+            options.level = self.connection.settings.level;
+        }
+        format!("/db/query{}", options.query_string())
+    }
+
+    async fn execute_uri(&mut self, uri: &str, statement: serde_json::Value, timeout: Option<Duration>) -> Result<(), RqliteError> {
+        let query_json = json!([statement]);
+        let result = self.send_with(uri, &query_json, timeout).await?;
+
+        self.reset();
+        self.pending = result.results.unwrap_or_default().into();
+        self.next_result().unwrap_or(Ok(()))
+    }
+
+    /// Make `item` the current result
+    fn load_result(&mut self, item: ResultMap) -> Result<(), RqliteError> {
+        self.reset();
+        self.timing = item.time.and_then(|t| Duration::try_from_secs_f64(t).ok());
+        if let Some(error) = item.error {
+            return Err(result_error(error));
+        }
+
+        match (item.columns, item.types) {
+            (Some(fields), Some(ColumnTypes::List(types))) => {
+                self.description = fields.into_iter().zip(types).collect();
+                self.columns     = Arc::new(self.description.iter().map(|(name, _)| name.clone()).collect());
+                self.types       = Arc::new(self.description.iter().map(|(_, _type)| *_type).collect());
+                self.rows        = item.values.unwrap_or_default();
+                self.rowcount    = self.rows.len() as isize;
+            },
+            (_, Some(ColumnTypes::Named(types))) => {
+                // associative result, rows are read in the order of the types
+                let rows = item.rows.unwrap_or_default().into_iter()
+                        .map(|mut row| types.iter().map(|(name, _)| row.remove(name).unwrap_or_default()).collect())
+                        .collect();
+                self.description = types;
+                self.columns     = Arc::new(self.description.iter().map(|(name, _)| name.clone()).collect());
+                self.types       = Arc::new(self.description.iter().map(|(_, _type)| *_type).collect());
+                self.rows        = rows;
+                self.rowcount    = self.rows.len() as isize;
             },
-            None => {}
+            _ => {
+                self.lastrowid = item.last_insert_id;
+                self.rowcount  = item.rows_affected.unwrap_or(0) as isize;
+            }
         }
+        Ok(())
+    }
 
+    /// Clear data of the current result
+    fn reset(&mut self) {
+        self.description.clear();
+        self.columns   = Arc::new(Vec::new());
+        self.types     = Arc::new(Vec::new());
+        self.rows.clear();
         self.rownumber = 0;
-        if query.starts_with("INSERT ") || query.starts_with("UPDATE ") || query.starts_with("DELETE ") {
-            self.rowcount = rows_affected;
-        } else {
-            self.rowcount = self.rows.len() as isize;
+        self.rowcount  = -1;
+        self.lastrowid = None;
+        self.timing    = None;
+    }
+
+    /// Execute multiple statements atomically in a single transaction
+    ///
+    /// All statements are sent in one request, if any of them fails
+    /// none of them is applied and the error of the failed statement is returned.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// .connect().await?;
+    /// let mut cur = conn.cursor();
+    /// let results = cur.execute_transaction(&[
+    ///     ("INSERT INTO foo(name) VALUES (?)", par!("fiona")),
+    ///     ("UPDATE bar SET count = count + 1", par!()),
+    /// ]).await?;
+    /// println!("fiona has id {:?}", results[0].last_insert_id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError on error to handle exception explicitly
+    pub async fn execute_transaction(&mut self, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<Vec<StatementResult>, RqliteError> {
+        let results = self.execute_statements("/db/execute?transaction", queries).await?;
+        match results.into_iter().collect::<Result<Vec<StatementResult>, StatementError>>() {
+            Ok(v) => Ok(v),
+            Err(e) => Err(RqliteError::SqlError(e.to_string()))
         }
+    }
+
+    /// Execute multiple statements in a single request
+    ///
+    /// Unlike [`Cursor::execute_transaction()`] statements are applied independently,
+    /// every statement has its own result which is an error if it failed.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// .connect().await?;
+    /// let mut cur = conn.cursor();
+    /// let results = cur.execute_batch(&[
+    ///     ("INSERT INTO foo(name) VALUES (?)", par!("fiona")),
+    ///     ("INSERT INTO nope(name) VALUES (?)", par!("diego")),
+    /// ]).await?;
+    /// for result in results {
+    ///     match result {
+    ///         Ok(v) => println!("inserted {:?}", v.last_insert_id),
+    ///         Err(e) => println!("statement {} failed: {}", e.index, e.message)
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError if the request fails
+    pub async fn execute_batch(&mut self, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<Vec<Result<StatementResult, StatementError>>, RqliteError> {
+        self.execute_statements("/db/execute", queries).await
+    }
+
+    /// Execute one statement with many parameter sets in a single request
+    ///
+    /// Statements are applied in a single transaction, [`Cursor::rows_affected()`]
+    /// returns the total of rows affected.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// .connect().await?;
+    /// let mut cur = conn.cursor();
+    /// cur.execute_many("INSERT INTO foo(name) VALUES (?)", vec![par!("fiona"), par!("diego")]).await?;
+    /// assert_eq!(cur.rows_affected(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError on error to handle exception explicitly
+    pub async fn execute_many(&mut self, query: &str, params: Vec<impl IntoParams>) -> Result<(), RqliteError> {
+        let queries = params.into_iter()
+                .map(|params| Ok((query, into_params(params)?)))
+                .collect::<Result<Vec<(&str, Vec<serde_json::Value>)>, RqliteError>>()?;
+        self.execute_transaction(&queries).await?;
         Ok(())
     }
 
+    async fn execute_statements(&mut self, uri: &str, queries: &[(&str, Vec<serde_json::Value>)]) -> Result<Vec<Result<StatementResult, StatementError>>, RqliteError> {
+        let query_json = serde_json::Value::Array(queries.iter()
+                .map(|(query, params)| statement(query, params.clone()))
+                .collect());
+        let result = self.send(uri, &query_json).await?;
+
+        let mut statements = Vec::with_capacity(queries.len());
+        for (index, item) in result.results.unwrap_or_default().into_iter().enumerate() {
+            statements.push(match item.error {
+                Some(message) => Err(StatementError { index, message }),
+                None => Ok(StatementResult {
+                    rows_affected: item.rows_affected.unwrap_or(0),
+                    last_insert_id: item.last_insert_id
+                })
+            });
+        }
+
+        self.reset();
+        self.pending.clear();
+        self.rowcount  = statements.iter().flatten().map(|s| s.rows_affected as isize).sum();
+        self.lastrowid = statements.iter().flatten().rev().find_map(|s| s.last_insert_id);
+        Ok(statements)
+    }
+
+    /// Queue statements for execution
+    ///
+    /// rqlite acknowledges queued writes before applying them, in batches with other queued writes.
+    /// With `wait` set to None the request returns immediately, otherwise it waits
+    /// at most `wait` for the statements to be applied.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// .connect().await?;
+    /// let mut cur = conn.cursor();
+    /// let seq = cur.execute_queued(&[("INSERT INTO foo(name) VALUES (?)", par!("fiona"))], None).await?;
+    /// println!("queued with sequence number {}", seq);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns the sequence number of the queued request, or RqliteError on error
+    pub async fn execute_queued(&mut self, queries: &[(&str, Vec<serde_json::Value>)], wait: Option<Duration>) -> Result<u64, RqliteError> {
+        let query_json = serde_json::Value::Array(queries.iter()
+                .map(|(query, params)| statement(query, params.clone()))
+                .collect());
+        let uri = match wait {
+            Some(timeout) => format!("/db/execute?queue&wait=true&timeout={}ms", timeout.as_millis()),
+            None => "/db/execute?queue".to_owned()
+        };
+        let result = self.send(&uri, &query_json).await?;
+
+        for (i, item) in result.results.unwrap_or_default().into_iter().enumerate() {
+            if let Some(error) = item.error {
+                return Err(RqliteError::SqlError(format!("statement {} failed: {}", i, error)));
+            }
+        }
+        self.reset();
+        self.pending.clear();
+        match result.sequence_number {
+            Some(v) => Ok(v),
+            None => Err(RqliteError::DataSer("Missing sequence_number in response".to_owned()))
+        }
+    }
+
+    /// Send statements to rqlite and parse the response
+    async fn send(&mut self, uri: &str, query_json: &serde_json::Value) -> Result<QueryResult, RqliteError> {
+        self.send_with(uri, query_json, None).await
+    }
+
+    /// Send statements, `timeout` overrides the connection request timeout
+    ///
+    /// Transient errors are retried according to the connection retry policy
+    async fn send_with(&mut self, uri: &str, query_json: &serde_json::Value, timeout: Option<Duration>) -> Result<QueryResult, RqliteError> {
+        let span = trace::query_span(uri, query_json, &self.connection.settings);
+        let policy = self.connection.settings.retry_policy.clone();
+        let start = Instant::now();
+        let result = trace::in_query(span, async {
+            let mut attempt = 0;
+            loop {
+                match self.send_once(uri, query_json, timeout, policy.can_retry(attempt)).await {
+                    Err(e) if policy.should_retry(attempt, &e) => {
+                        tokio::time::sleep(policy.delay(attempt)).await;
+                        attempt += 1;
+                    },
+                    result => return result
+                }
+            }
+        }).await;
+        if let Ok(v) = &result {
+            self.raft_index = v.raft_index;
+        }
+        match &result {
+            Ok(v) => stats::rows_returned(v.results.iter().flatten().map(|r| r.values.as_ref().map_or(0, Vec::len) + r.rows.as_ref().map_or(0, Vec::len)).sum()),
+            Err(e) => stats::error(e)
+        }
+        let duration = start.elapsed();
+        if hooks::wants(&self.connection.settings, duration) {
+            let errors = match &result {
+                Ok(v) => v.results.iter().flatten().map(|r| r.error.clone()).collect(),
+                Err(e) => vec![Some(e.to_string())]
+            };
+            hooks::on_query(&self.connection.settings, query_json, duration, &errors);
+        }
+        result
+    }
+
+    /// Send statements once
+    ///
+    /// With `retrying`, statement errors caused by a leadership change are returned as RqliteError
+    async fn send_once(&mut self, uri: &str, query_json: &serde_json::Value, timeout: Option<Duration>, retrying: bool) -> Result<QueryResult, RqliteError> {
+        let params = self.connection.request_options.statement_params();
+        let mut req_builder = match (params.is_empty(), uri.contains('?')) {
+            (true, _) => Request::builder().method("POST").uri(uri),
+            (false, true) => Request::builder().method("POST").uri(format!("{}&{}", uri, params.join("&"))),
+            (false, false) => Request::builder().method("POST").uri(format!("{}?{}", uri, params.join("&")))
+        };
+        req_builder = self.connection.auth(self.connection.base_headers(req_builder));
+        let connection = self.connection;
+        let (status, body) = with_timeout(timeout.or(connection.settings.request_timeout), async {
+            let resp = connection.request(req_builder, Some(query_json)).await?;
+            let status = resp.status().as_u16();
+            Ok((status, connection.read_body(resp).await?))
+        }).await?;
+        if status != 200 {
+            return Err(status_error(status, &body));
+        }
+        let mut result: QueryResult = self.connection.body(body).await?;
+        if let Some(error) = result.error.take() {
+            return Err(result_error(error));
+        }
+
+        if retrying && self.connection.settings.retry_policy.retries(ErrorClass::Leadership) {
+            let results = result.results.iter().flatten();
+            if let Some(error) = results.filter_map(|r| r.error.as_ref()).find(|e| is_leadership_error(e)) {
+                return Err(RqliteError::SqlError(error.clone()));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Execution time of the current result on the node
+    ///
+    /// Only reported when requested with [`RequestOptions::timings()`](crate::RequestOptions::timings)
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let timed = conn.with_options(RequestOptions::new().timings(true));
+    /// let cur = timed.query("SELECT * FROM foo", par!()).await?;
+    /// println!("{:?}", cur.timing());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn timing(&self) -> Option<Duration> {
+        self.timing
+    }
+
+    /// Raft log index of the last write, to wait for followers to apply it
+    ///
+    /// Only reported when requested with [`RequestOptions::raft_index()`](crate::RequestOptions::raft_index),
+    /// queued writes return a sequence number instead, see [`Cursor::execute_queued()`].
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let indexed = conn.with_options(RequestOptions::new().raft_index(true));
+    /// let cur = indexed.execute("INSERT INTO foo(name) VALUES (?)", par!("fiona")).await?;
+    /// println!("{:?}", cur.raft_index());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn raft_index(&self) -> Option<u64> {
+        self.raft_index
+    }
+
     /// Return number of rows read
     pub fn rows_read(&self) -> usize {
         self.rownumber
@@ -151,22 +686,59 @@ impl<'l> Cursor<'l> {
 
     /// Returns a slice of column and type tuples
     /// Empty slice returned when no select request is made
-    pub fn description<'a>(&'a self) -> &'a [(String, Type)] {
+    pub fn description(&self) -> &[(String, Type)] {
         &self.description
     }
 
+    /// Returns column names of the last query result
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let cur = conn.query("SELECT id, name FROM foo", par!()).await?;
+    /// assert_eq!(cur.columns(), ["id", "name"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Returns column types of the last query result, in the same order as [`Cursor::columns()`]
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let cur = conn.query("SELECT id, name FROM foo", par!()).await?;
+    /// for (name, _type) in cur.columns().iter().zip(cur.types()) {
+    ///     println!("{}: {:?}", name, _type);
+    /// }
+    /// // id: Integer
+    /// // name: Text
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+
     /// Returns last row id
     /// Valid only for insert query
     pub fn last_row_id(&self) -> Option<usize> {
-        self.lastrowid.clone()
+        self.lastrowid
     }
 
     /// Get next row
-    /// ```
-    /// let mut conn = ConnectOptions::new("127.0.0.1", 4001)
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
     /// .connect().await?;
-    /// let cur = conn.cursor();
-    /// cur.execute("SELECT * FROM foo", par!())?;
+    /// let mut cur = conn.cursor();
+    /// cur.query("SELECT * FROM foo", par!()).await?;
     /// let mut i: i32;
     /// let mut str: String;
     /// while let Some(row) = cur.next_row() {
@@ -176,13 +748,311 @@ impl<'l> Cursor<'l> {
     /// }
     /// // 0 | fiona
     /// // 1 | diego
+    /// # Ok(())
+    /// # }
     /// ```
     ///
     pub fn next_row(&mut self) -> Option<Row> {
-        if self.rows.len() == 0 {
+        if self.rows.is_empty() {
             return None;
         }
         self.rownumber += 1;
-        Some(Row::new(self.rows.remove(0)))
+        Some(Row::new(self.columns.clone(), self.types.clone(), self.rows.remove(0)))
+    }
+
+    /// Get all remaining rows
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let mut cur = conn.cursor();
+    /// cur.query("SELECT * FROM foo", par!()).await?;
+    /// for row in cur.fetch_all() {
+    ///     println!("{}", row.get::<String>(1)?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fetch_all(&mut self) -> Vec<Row> {
+        let rows = std::mem::take(&mut self.rows);
+        self.rownumber += rows.len();
+        rows.into_iter()
+            .map(|row| Row::new(self.columns.clone(), self.types.clone(), row))
+            .collect()
+    }
+
+    /// Get the only remaining row
+    ///
+    /// Returns RqliteError if there is no row or more than one
+    pub fn fetch_one(&mut self) -> Result<Row, RqliteError> {
+        match self.fetch_optional()? {
+            Some(row) => Ok(row),
+            None => Err(RqliteError::ResultShape("Expected one row, got none".to_owned()))
+        }
+    }
+
+    /// Get the only remaining row, if any
+    ///
+    /// Returns RqliteError if there is more than one row
+    pub fn fetch_optional(&mut self) -> Result<Option<Row>, RqliteError> {
+        if self.rows.len() > 1 {
+            return Err(RqliteError::ResultShape(format!("Expected at most one row, got {}", self.rows.len())));
+        }
+        Ok(self.next_row())
+    }
+
+    /// Get first column of the next row
+    ///
+    /// Returns RqliteError if there is no row or no column, or if the value cannot be formatted
+    pub fn fetch_scalar<T: DeserializeOwned>(&mut self) -> Result<T, RqliteError> {
+        let row = match self.next_row() {
+            Some(v) => v,
+            None => return Err(RqliteError::ResultShape("Expected a row, got none".to_owned()))
+        };
+        if row.columns().is_empty() {
+            return Err(RqliteError::ResultShape("Expected a column, got none".to_owned()));
+        }
+        match row.get(0) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(RqliteError::DataSer(e.to_string()))
+        }
+    }
+
+    /// Deserialize all remaining rows into `T`
+    ///
+    /// Each row is deserialized from a map of column names to values,
+    /// so fields of `T` are matched by column name.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # use serde::Deserialize;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// #[derive(Deserialize)]
+    /// struct Foo {
+    ///     id: i64,
+    ///     name: String
+    /// }
+    ///
+    /// let mut cur = conn.cursor();
+    /// cur.query("SELECT id, name FROM foo", par!()).await?;
+    /// let foos: Vec<Foo> = cur.fetch_all_as()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError if a row cannot be deserialized into `T`
+    pub fn fetch_all_as<T: DeserializeOwned>(&mut self) -> Result<Vec<T>, RqliteError> {
+        let rows = std::mem::take(&mut self.rows);
+        self.rownumber += rows.len();
+
+        let mut vec = Vec::with_capacity(rows.len());
+        for row in rows {
+            let object: serde_json::Map<String, serde_json::Value> = self.description.iter()
+                    .map(|(name, _)| name.clone())
+                    .zip(row)
+                    .collect();
+            vec.push(match serde_json::from_value(serde_json::Value::Object(object)) {
+                Ok(v) => v,
+                Err(e) => return Err(RqliteError::Json(e))
+            });
+        }
+        Ok(vec)
+    }
+
+    /// Take the remaining rows and the current result, to keep them after the cursor
+    /// ```no_run
+    /// # use rqlite::{par, ConnectOptions};
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let rows = conn.query("SELECT * FROM foo", par!()).await?.into_rows();
+    /// let task = tokio::spawn(async move {
+    ///     for row in rows {
+    ///         println!("{}", row.get::<String>(1)?);
+    ///     }
+    ///     Ok::<(), Error>(())
+    /// });
+    /// task.await??;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_rows(self) -> Rows {
+        Rows {
+            columns: self.columns,
+            types: self.types,
+            rows: self.rows.into(),
+            rows_affected: self.rowcount,
+            last_row_id: self.lastrowid,
+            timing: self.timing,
+            raft_index: self.raft_index
+        }
+    }
+
+    /// Execute sql read query and deserialize all rows into `T`
+    ///
+    /// See [`Cursor::fetch_all_as()`]
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// # #[derive(serde::Deserialize)]
+    /// # struct Foo { id: i64, name: String }
+    /// let mut cur = conn.cursor();
+    /// let foos: Vec<Foo> = cur.query_as("SELECT id, name FROM foo WHERE id > ?", par!(5)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError on error to handle exception explicitly
+    pub async fn query_as<T: DeserializeOwned>(&mut self, query: &str, params: impl IntoParams) -> Result<Vec<T>, RqliteError> {
+        self.query(query, params).await?;
+        self.fetch_all_as()
+    }
+}
+
+/// Stream of the remaining rows
+///
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// use futures::StreamExt;
+///
+/// let mut cur = conn.query("SELECT * FROM foo", par!()).await?;
+/// while let Some(row) = cur.next().await {
+///     let name: String = row?.get(1)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+impl Stream for Cursor<'_> {
+    type Item = Result<Row, RqliteError>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().next_row().map(Ok))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.rows.len(), Some(self.rows.len()))
+    }
+}
+
+/// Owned result of a statement, independent of the [`Connection`]
+///
+/// Created by [`Cursor::into_rows()`] or [`Connection::query_rows()`], it can be sent
+/// to another task while the connection is used. Iterating yields the remaining rows.
+#[derive(Clone, Debug)]
+pub struct Rows {
+    columns: Arc<Vec<String>>,
+    types: Arc<Vec<Type>>,
+    rows: VecDeque<Vec<serde_json::Value>>,
+    rows_affected: isize,
+    last_row_id: Option<usize>,
+    timing: Option<Duration>,
+    raft_index: Option<u64>
+}
+
+impl Rows {
+    /// Column names of the result
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Column types of the result, in the same order as [`Rows::columns()`]
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+
+    /// Number of remaining rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Check if there are no remaining rows
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Number of rows affected/got from the statement, see [`Cursor::rows_affected()`]
+    pub fn rows_affected(&self) -> isize {
+        self.rows_affected
+    }
+
+    /// Last row id of an insert, see [`Cursor::last_row_id()`]
+    pub fn last_row_id(&self) -> Option<usize> {
+        self.last_row_id
+    }
+
+    /// Execution time on the node, see [`Cursor::timing()`]
+    pub fn timing(&self) -> Option<Duration> {
+        self.timing
+    }
+
+    /// Raft log index of the write, see [`Cursor::raft_index()`]
+    pub fn raft_index(&self) -> Option<u64> {
+        self.raft_index
+    }
+}
+
+impl Iterator for Rows {
+    type Item = Row;
+
+    fn next(&mut self) -> Option<Row> {
+        let row = self.rows.pop_front()?;
+        Some(Row::new(self.columns.clone(), self.types.clone(), row))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.rows.len(), Some(self.rows.len()))
+    }
+}
+
+impl ExactSizeIterator for Rows {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::time::Duration;
+    use hyper::{Body, Response, Server};
+    use hyper::service::{make_service_fn, service_fn};
+    use crate::connect::ConnectOptions;
+    use crate::options::{Level, QueryOptions};
+
+    /// Port of a node answering every request with `body`
+    fn serve(body: &'static str) -> u16 {
+        let make = make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |_| async move { Ok::<_, Infallible>(Response::new(Body::from(body))) }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make);
+        let port = server.local_addr().port();
+        tokio::spawn(server);
+        port
+    }
+
+    #[test]
+    fn top_level_error_is_parsed() {
+        let result: QueryResult = serde_json::from_str(r#"{"error": "stale read"}"#).unwrap();
+        assert_eq!(result.error.as_deref(), Some("stale read"));
+        assert!(result.results.is_none());
+    }
+
+    #[tokio::test]
+    async fn stale_read_is_an_error() {
+        let conn = ConnectOptions::new("127.0.0.1", serve(r#"{"error": "stale read"}"#)).connect().await.unwrap();
+        let options = QueryOptions::new().level(Level::None).freshness(Duration::from_secs(1)).clone();
+        let result = conn.query_with("SELECT * FROM foo", crate::par!(), &options).await;
+        assert!(matches!(result, Err(RqliteError::StaleRead)));
+    }
+
+    #[tokio::test]
+    async fn top_level_error_of_execute() {
+        let conn = ConnectOptions::new("127.0.0.1", serve(r#"{"error": "database is closed"}"#)).connect().await.unwrap();
+        let result = conn.execute("INSERT INTO foo(name) VALUES (?)", crate::par!("fiona")).await;
+        assert!(matches!(result, Err(RqliteError::SqlError(e)) if e == "database is closed"));
     }
 }
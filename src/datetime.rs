@@ -0,0 +1,160 @@
+//! Date and time parameters and columns, with the `chrono` or `time` feature
+//!
+//! Values are sent as `YYYY-MM-DD HH:MM:SS.SSS` text in UTC, the format of sqlite date functions.
+//! Columns are read from this format, RFC 3339 text or unix timestamps in seconds.
+#![cfg(any(feature = "chrono", feature = "time"))]
+use serde_json::Value;
+use crate::types::ToParam;
+
+#[cfg(feature = "chrono")]
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> ToParam for chrono::DateTime<Tz> {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::String(self.naive_utc().format(DATETIME_FORMAT).to_string()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToParam for chrono::NaiveDateTime {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::String(self.format(DATETIME_FORMAT).to_string()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToParam for chrono::NaiveDate {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::String(self.format("%Y-%m-%d").to_string()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToParam for chrono::NaiveTime {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::String(self.format("%H:%M:%S%.f").to_string()))
+    }
+}
+
+#[cfg(feature = "time")]
+impl ToParam for time::OffsetDateTime {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        let utc = self.to_offset(time::UtcOffset::UTC);
+        time::PrimitiveDateTime::new(utc.date(), utc.time()).to_param()
+    }
+}
+
+#[cfg(feature = "time")]
+impl ToParam for time::PrimitiveDateTime {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::String(format!("{} {}", format_date(self.date()), format_time(self.time()))))
+    }
+}
+
+#[cfg(feature = "time")]
+impl ToParam for time::Date {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::String(format_date(*self)))
+    }
+}
+
+#[cfg(feature = "time")]
+impl ToParam for time::Time {
+    fn to_param(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::String(format_time(*self)))
+    }
+}
+
+#[cfg(feature = "time")]
+fn format_date(date: time::Date) -> String {
+    format!("{:04}-{:02}-{:02}", date.year(), u8::from(date.month()), date.day())
+}
+
+/// `HH:MM:SS`, with as many fraction digits as chrono's `%.f`
+#[cfg(feature = "time")]
+fn format_time(time: time::Time) -> String {
+    let nanos = time.nanosecond();
+    let fraction = if nanos == 0 {
+        String::new()
+    } else if nanos % 1_000_000 == 0 {
+        format!(".{:03}", nanos / 1_000_000)
+    } else if nanos % 1_000 == 0 {
+        format!(".{:06}", nanos / 1_000)
+    } else {
+        format!(".{:09}", nanos)
+    };
+    format!("{:02}:{:02}:{:02}{}", time.hour(), time.minute(), time.second(), fraction)
+}
+
+/// Sqlite text dates use a space instead of `T` and no offset, timestamps are numbers
+#[cfg(feature = "chrono")]
+pub(crate) fn chrono_alternatives(value: &Value) -> Vec<Value> {
+    let mut values = Vec::new();
+    match value {
+        Value::String(s) if is_datetime(s) => {
+            let rfc3339 = format!("{}T{}", &s[..10], &s[11..]);
+            if !has_offset(s) {
+                values.push(Value::String(format!("{}Z", rfc3339)));
+                values.push(Value::String(rfc3339));
+            } else if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(&rfc3339) {
+                // naive values are in UTC
+                values.push(Value::String(rfc3339));
+                values.push(Value::String(datetime.naive_utc().format("%Y-%m-%dT%H:%M:%S%.f").to_string()));
+            }
+        },
+        Value::Number(n) => {
+            if let Some(datetime) = n.as_i64().and_then(|n| chrono::DateTime::from_timestamp(n, 0)) {
+                values.push(Value::String(datetime.format("%Y-%m-%dT%H:%M:%SZ").to_string()));
+                values.push(Value::String(datetime.format("%Y-%m-%dT%H:%M:%S").to_string()));
+            }
+        },
+        _ => ()
+    }
+    values
+}
+
+/// Values are parsed and serialized again, in the format of time's serde implementation
+#[cfg(feature = "time")]
+pub(crate) fn time_alternatives(value: &Value) -> Vec<Value> {
+    use time::format_description::well_known::Rfc3339;
+    use time::OffsetDateTime;
+
+    let datetime = match value {
+        Value::String(s) if is_datetime(s) => {
+            let mut rfc3339 = format!("{}T{}", &s[..10], &s[11..]);
+            if !has_offset(s) {
+                rfc3339.push('Z');
+            }
+            OffsetDateTime::parse(&rfc3339, &Rfc3339).ok()
+        },
+        // time of day
+        Value::String(s) if s.len() >= 8 && s.as_bytes()[2] == b':' => {
+            let time = OffsetDateTime::parse(&format!("1970-01-01T{}Z", s), &Rfc3339).ok().map(|v| v.time());
+            return time.and_then(|v| serde_json::to_value(v).ok()).into_iter().collect();
+        },
+        Value::Number(n) => n.as_i64().and_then(|n| OffsetDateTime::from_unix_timestamp(n).ok()),
+        _ => None
+    };
+    let datetime = match datetime {
+        Some(v) => v,
+        None => return Vec::new()
+    };
+    // primitive values are in UTC
+    let utc = datetime.to_offset(time::UtcOffset::UTC);
+    vec![serde_json::to_value(datetime), serde_json::to_value(time::PrimitiveDateTime::new(utc.date(), utc.time()))]
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect()
+}
+
+/// Check if `s` starts with `YYYY-MM-DD HH:MM:SS` or `YYYY-MM-DDTHH:MM:SS`
+fn is_datetime(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() >= 19 && bytes[4] == b'-' && bytes[7] == b'-' && matches!(bytes[10], b' ' | b'T') && bytes[13] == b':'
+}
+
+/// Check if a datetime ends with `Z` or `+HH:MM`
+fn has_offset(s: &str) -> bool {
+    s.get(19..).is_some_and(|rest| rest.contains(['+', '-', 'Z', 'z']))
+}
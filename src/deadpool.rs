@@ -0,0 +1,60 @@
+//! [deadpool](https://docs.rs/deadpool) manager for connections, with the `deadpool` feature
+//! ```no_run
+//! # use rqlite::*;
+//! # type Error = Box<dyn std::error::Error + Send + Sync>;
+//! # async fn run() -> Result<(), Error> {
+//! use rqlite::ConnectOptions;
+//! use rqlite::deadpool::{Manager, Pool};
+//!
+//! let pool = Pool::builder(Manager::new(&ConnectOptions::new("127.0.0.1", 4001)))
+//!     .max_size(16)
+//!     .build()?;
+//! let conn = pool.get().await?;
+//! conn.execute("INSERT INTO foo(name) VALUES (?)", par!("fiona")).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::Arc;
+use ::deadpool::managed::{self, Metrics, RecycleError, RecycleResult};
+use crate::auth::Auth;
+use crate::connect::{ConnectOptions, Connection};
+use crate::error::RqliteError;
+
+/// Deadpool pool of rqlite connections
+pub type Pool = managed::Pool<Manager>;
+
+/// Creates connections, and checks them with `/readyz` before they are reused
+///
+/// Connections of a manager share credentials, see [`Connection::set_credentials()`].
+#[derive(Debug)]
+pub struct Manager {
+    options: ConnectOptions,
+    auth: Arc<Auth>
+}
+
+impl Manager {
+    /// Create a manager opening connections with `options`
+    pub fn new(options: &ConnectOptions) -> Manager {
+        Manager { options: options.clone(), auth: Arc::new(options.auth()) }
+    }
+}
+
+impl managed::Manager for Manager {
+    type Type = Connection;
+    type Error = RqliteError;
+
+    async fn create(&self) -> Result<Connection, RqliteError> {
+        self.options.connect_with_auth(self.auth.clone()).await
+    }
+
+    async fn recycle(&self, conn: &mut Connection, _metrics: &Metrics) -> RecycleResult<RqliteError> {
+        if conn.is_closed().await {
+            return Err(RecycleError::message("Connection closed"));
+        }
+        match conn.ready().await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(RecycleError::message("Node not ready")),
+            Err(e) => Err(RecycleError::Backend(e))
+        }
+    }
+}
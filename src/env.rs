@@ -0,0 +1,88 @@
+use std::env::{self, VarError};
+use crate::connect::{ConnectOptions, Scheme};
+use crate::error::RqliteError;
+use crate::url::{config, parse_duration, parse_host, parse_value, DEFAULT_PORT};
+
+/// Hosts used when `RQLITE_HOSTS` is not set
+const DEFAULT_HOSTS: &str = "localhost:4001";
+
+impl ConnectOptions {
+    /// Create connection options from environment variables
+    ///
+    /// - `RQLITE_HOSTS`: comma separated `host[:port]` list, defaults to `localhost:4001`
+    /// - `RQLITE_SCHEME`: `http` or `https`
+    /// - `RQLITE_USER`, `RQLITE_PASSWORD`: basic auth credentials
+    /// - `RQLITE_TOKEN`: bearer token, replacing basic auth
+    /// - `RQLITE_LEVEL`: default read consistency level
+    /// - `RQLITE_TIMEOUT`, `RQLITE_CONNECT_TIMEOUT`: durations, like `5s` or `500ms`
+    /// - `RQLITE_MAX_REDIRECTS`: max redirects followed by a request
+    /// - `RQLITE_ACCEPT_INVALID_CERT`, `RQLITE_HTTP2`: `true` or `false`
+    /// - `RQLITE_CA_CERT_FILE`: path of a PEM file with certificates to trust
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::from_env()?.connect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns `RqliteError::Config` if a variable is invalid
+    pub fn from_env() -> Result<ConnectOptions, RqliteError> {
+        let hosts = var("RQLITE_HOSTS")?.unwrap_or_else(|| DEFAULT_HOSTS.to_owned());
+        let nodes = hosts.split(',')
+                .map(|host| parse_host(host.trim(), DEFAULT_PORT))
+                .collect::<Result<Vec<(String, u16)>, RqliteError>>()?;
+        let nodes: Vec<(&str, u16)> = nodes.iter().map(|(host, port)| (host.as_str(), *port)).collect();
+        let mut options = ConnectOptions::nodes(&nodes)?;
+
+        if let Some(scheme) = var("RQLITE_SCHEME")? {
+            options.scheme(match scheme.to_ascii_lowercase().as_str() {
+                "http"  => Scheme::HTTP,
+                "https" => Scheme::HTTPS,
+                _ => return Err(config(format!("Unsupported scheme {}", scheme)))
+            });
+        }
+        if let Some(user) = var("RQLITE_USER")? {
+            options.user(&user);
+        }
+        if let Some(pass) = var("RQLITE_PASSWORD")? {
+            options.pass(&pass);
+        }
+        if let Some(token) = var("RQLITE_TOKEN")? {
+            options.bearer_token(&token);
+        }
+        if let Some(level) = var("RQLITE_LEVEL")? {
+            options.level(level.parse()?);
+        }
+        if let Some(timeout) = var("RQLITE_TIMEOUT")? {
+            options.request_timeout(parse_duration(&timeout)?);
+        }
+        if let Some(timeout) = var("RQLITE_CONNECT_TIMEOUT")? {
+            options.connect_timeout(parse_duration(&timeout)?);
+        }
+        if let Some(redirects) = var("RQLITE_MAX_REDIRECTS")? {
+            options.max_redirects(parse_value("RQLITE_MAX_REDIRECTS", &redirects)?);
+        }
+        if let Some(accept) = var("RQLITE_ACCEPT_INVALID_CERT")? {
+            options.accept_invalid_cert(parse_value("RQLITE_ACCEPT_INVALID_CERT", &accept)?);
+        }
+        if let Some(http2) = var("RQLITE_HTTP2")? {
+            options.http2(parse_value("RQLITE_HTTP2", &http2)?);
+        }
+        if let Some(path) = var("RQLITE_CA_CERT_FILE")? {
+            options.ca_cert_file(path);
+        }
+        Ok(options)
+    }
+}
+
+/// Value of variable `key`, None if it is unset or empty
+fn var(key: &str) -> Result<Option<String>, RqliteError> {
+    match env::var(key) {
+        Ok(v) if v.is_empty() => Ok(None),
+        Ok(v) => Ok(Some(v)),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(VarError::NotUnicode(_)) => Err(config(format!("Invalid utf-8 in {}", key)))
+    }
+}
@@ -1,6 +1,9 @@
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, io};
+use crate::retry::{is_leadership_error, ErrorClass};
 
 /// Handle all errors for Connection and Cursor explicitly
+///
+/// Errors of the underlying http, json and io libraries are kept and returned by [`Error::source()`].
 #[derive(Debug)]
 pub enum RqliteError {
     /// Error in authentification
@@ -13,13 +16,200 @@ pub enum RqliteError {
     ///
     /// Some changes need to be made to this crate
     DataSer(String),
+    /// Serializing parameters or parsing a response failed
+    Json(serde_json::Error),
     /// Connection error, either there is some networking error.
     ///
     /// Or one of the ends closed connection
-    Connection(String)
+    Connection(String),
+    /// Sending a request or reading a response failed
+    Http(hyper::Error),
+    /// Reading or writing a file or stream failed, like the writer of a backup
+    Io(io::Error),
+    /// Node answered with an unexpected HTTP status
+    Status {
+        /// HTTP status of the response
+        status: u16,
+        /// Body of the response, usually the error reported by rqlite
+        body: String
+    },
+    /// Query result doesn't have the expected shape,
+    /// like a different number of rows or columns
+    ResultShape(String),
+    /// Host name of the node could not be resolved
+    Dns {
+        /// Host name of the node
+        host: String,
+        /// Resolver error
+        source: io::Error
+    },
+    /// Tcp connection to the node, its proxy or through its transport failed
+    Tcp {
+        /// `host:port` of the node
+        addr: String,
+        /// Socket, proxy or transport error
+        source: Box<dyn Error + Send + Sync>
+    },
+    /// Tls handshake failed or tls could not be configured
+    Tls(Box<dyn Error + Send + Sync>),
+    /// HTTP handshake with the node failed
+    Handshake(hyper::Error),
+    /// Connecting or waiting for a response took longer than the configured timeout
+    Timeout,
+    /// The cluster has no leader
+    NoLeader,
+    /// Request must be sent to the leader and the redirect was not followed,
+    /// because of [`ConnectOptions::max_redirects()`](crate::ConnectOptions::max_redirects)
+    NotLeader {
+        /// Address of the leader reported by the node, like `http://10.0.0.2:4001`
+        leader: Option<String>
+    },
+    /// The node data is older than the freshness of the query
+    StaleRead,
+    /// Response body is larger than the limit in bytes,
+    /// see [`ConnectOptions::max_response_size()`](crate::ConnectOptions::max_response_size)
+    ResponseTooLarge(usize),
+    /// Invalid configuration, like a malformed connection url
+    Config(String),
+    /// Cluster management operation refused by rqlite
+    ClusterOp {
+        /// HTTP status of the response
+        status: u16,
+        /// Error reported by rqlite
+        message: String
+    }
+}
+
+/// Kind of a SQLite error reported by rqlite, see [`RqliteError::sqlite_kind()`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqliteErrorKind {
+    /// `UNIQUE constraint failed`, also raised for primary keys
+    UniqueConstraint,
+    /// `NOT NULL constraint failed`
+    NotNullConstraint,
+    /// `FOREIGN KEY constraint failed`
+    ForeignKeyConstraint,
+    /// `CHECK constraint failed`
+    CheckConstraint,
+    /// Other constraint failure
+    Constraint,
+    /// `no such table`
+    NoSuchTable,
+    /// `no such column`, or `has no column named` on insert
+    NoSuchColumn,
+    /// Invalid sql, like `near "SELEC": syntax error` or `incomplete input`
+    Syntax,
+    /// `database is locked` or busy
+    Locked,
+    /// Any other error
+    Other
+}
+
+impl SqliteErrorKind {
+    /// Kind of an error message reported by rqlite
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// assert_eq!(SqliteErrorKind::from_message("UNIQUE constraint failed: foo.id"), SqliteErrorKind::UniqueConstraint);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_message(message: &str) -> SqliteErrorKind {
+        if message.contains("UNIQUE constraint failed") {
+            SqliteErrorKind::UniqueConstraint
+        } else if message.contains("NOT NULL constraint failed") {
+            SqliteErrorKind::NotNullConstraint
+        } else if message.contains("FOREIGN KEY constraint failed") {
+            SqliteErrorKind::ForeignKeyConstraint
+        } else if message.contains("CHECK constraint failed") {
+            SqliteErrorKind::CheckConstraint
+        } else if message.contains("constraint failed") {
+            SqliteErrorKind::Constraint
+        } else if message.contains("no such table") {
+            SqliteErrorKind::NoSuchTable
+        } else if message.contains("no such column") || message.contains("has no column named") {
+            SqliteErrorKind::NoSuchColumn
+        } else if message.contains("syntax error") || message.contains("incomplete input") {
+            SqliteErrorKind::Syntax
+        } else if message.contains("database is locked") || message.contains("database table is locked") || message.contains("database is busy") {
+            SqliteErrorKind::Locked
+        } else {
+            SqliteErrorKind::Other
+        }
+    }
+
+    /// Check if a constraint of the table was violated
+    pub fn is_constraint(&self) -> bool {
+        matches!(self, SqliteErrorKind::UniqueConstraint | SqliteErrorKind::NotNullConstraint
+                | SqliteErrorKind::ForeignKeyConstraint | SqliteErrorKind::CheckConstraint | SqliteErrorKind::Constraint)
+    }
+}
+
+impl RqliteError {
+    /// Check if the error is transient and the request can be sent again,
+    /// like a connection error, a timeout or a leadership change
+    ///
+    /// This is the classification used by [`RetryPolicy`](crate::RetryPolicy), whatever classes it retries.
+    pub fn is_retryable(&self) -> bool {
+        ErrorClass::of(self).is_some()
+    }
+
+    /// Check if the credentials were refused
+    pub fn is_auth(&self) -> bool {
+        matches!(self, RqliteError::AuthError | RqliteError::Status { status: 401 | 403, .. })
+    }
+
+    /// Check if the node is not the leader, the cluster has no leader or leadership changed
+    pub fn is_not_leader(&self) -> bool {
+        match self {
+            RqliteError::NoLeader | RqliteError::NotLeader { .. } => true,
+            RqliteError::SqlError(v) | RqliteError::Status { body: v, .. } => is_leadership_error(v),
+            _ => false
+        }
+    }
+
+    /// Check if the request or connection timed out
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, RqliteError::Timeout)
+    }
+
+    /// Kind of the SQLite error of a statement, None if the error is not a `SqlError`
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// match conn.execute("INSERT INTO foo(id) VALUES (?)", par!(1)).await {
+    ///     Ok(_) => println!("inserted"),
+    ///     Err(e) if e.sqlite_kind() == Some(SqliteErrorKind::UniqueConstraint) => println!("already inserted"),
+    ///     Err(e) => return Err(e.into())
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sqlite_kind(&self) -> Option<SqliteErrorKind> {
+        match self {
+            RqliteError::SqlError(v) => Some(SqliteErrorKind::from_message(v)),
+            _ => None
+        }
+    }
 }
 
-impl Error for RqliteError {}
+impl Error for RqliteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RqliteError::Json(e)                 => Some(e),
+            RqliteError::Http(e)                 => Some(e),
+            RqliteError::Handshake(e)            => Some(e),
+            RqliteError::Io(e)                   => Some(e),
+            RqliteError::Dns { source, .. }      => Some(source),
+            RqliteError::Tcp { source, .. }      => Some(source.as_ref()),
+            RqliteError::Tls(e)                  => Some(e.as_ref()),
+            _ => None
+        }
+    }
+}
 
 impl fmt::Display for RqliteError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -27,7 +217,97 @@ impl fmt::Display for RqliteError {
             RqliteError::AuthError     => write!(f, "Error authentificating"),
             RqliteError::SqlError(v)   => write!(f, "Error executing sql query: {}", v),
             RqliteError::DataSer(v)    => write!(f, "Error with json: {}", v),
-            RqliteError::Connection(v) => write!(f, "Connection error: {}", v)
+            RqliteError::Json(e)       => write!(f, "Error with json: {}", e),
+            RqliteError::Connection(v) => write!(f, "Connection error: {}", v),
+            RqliteError::Http(e)       => write!(f, "Connection error: {}", e),
+            RqliteError::Io(e)         => write!(f, "Io error: {}", e),
+            RqliteError::Status { status, body } => write!(f, "Request failed with status {}: {}", status, body),
+            RqliteError::ResultShape(v) => write!(f, "Unexpected result: {}", v),
+            RqliteError::Dns { host, source } => write!(f, "Could not resolve host {}: {}", host, source),
+            RqliteError::Tcp { addr, source } => write!(f, "Tcp connection to {} failed: {}", addr, source),
+            RqliteError::Tls(e)        => write!(f, "Tls error: {}", e),
+            RqliteError::Handshake(e)  => write!(f, "HTTP handshake failed: {}", e),
+            RqliteError::Timeout       => write!(f, "Operation timed out"),
+            RqliteError::NoLeader      => write!(f, "Cluster has no leader"),
+            RqliteError::NotLeader { leader: Some(v) } => write!(f, "Node is not the leader, leader is {}", v),
+            RqliteError::NotLeader { leader: None } => write!(f, "Node is not the leader"),
+            RqliteError::StaleRead     => write!(f, "Node data is older than the requested freshness"),
+            RqliteError::ResponseTooLarge(v) => write!(f, "Response is larger than {} bytes", v),
+            RqliteError::Config(v)     => write!(f, "Invalid configuration: {}", v),
+            RqliteError::ClusterOp { status, message } => write!(f, "Cluster operation failed with status {}: {}", status, message)
         }
     }
 }
+
+impl From<serde_json::Error> for RqliteError {
+    fn from(e: serde_json::Error) -> RqliteError {
+        RqliteError::Json(e)
+    }
+}
+
+impl From<hyper::Error> for RqliteError {
+    fn from(e: hyper::Error) -> RqliteError {
+        RqliteError::Http(e)
+    }
+}
+
+impl From<io::Error> for RqliteError {
+    fn from(e: io::Error) -> RqliteError {
+        RqliteError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Errors of statements as reported in rqlite `results`
+    const RESULTS: &str = r#"{"results": [
+        {"error": "UNIQUE constraint failed: foo.id"},
+        {"error": "UNIQUE constraint failed: users.email, users.tenant"},
+        {"error": "NOT NULL constraint failed: foo.name"},
+        {"error": "FOREIGN KEY constraint failed"},
+        {"error": "CHECK constraint failed: age > 0"},
+        {"error": "constraint failed"},
+        {"error": "no such table: bar"},
+        {"error": "no such column: nmae"},
+        {"error": "table foo has no column named nmae"},
+        {"error": "near \"SELEC\": syntax error"},
+        {"error": "incomplete input"},
+        {"error": "database is locked"},
+        {"error": "database table is locked: foo"},
+        {"error": "datatype mismatch"},
+        {"error": "ambiguous column name: id"}
+    ], "time": 0.001362}"#;
+
+    #[test]
+    fn kinds_of_rqlite_errors() {
+        use SqliteErrorKind::*;
+        let json: serde_json::Value = serde_json::from_str(RESULTS).unwrap();
+        let kinds: Vec<SqliteErrorKind> = json["results"].as_array().unwrap().iter()
+                .map(|result| SqliteErrorKind::from_message(result["error"].as_str().unwrap()))
+                .collect();
+        assert_eq!(kinds, [
+            UniqueConstraint, UniqueConstraint, NotNullConstraint, ForeignKeyConstraint, CheckConstraint, Constraint,
+            NoSuchTable, NoSuchColumn, NoSuchColumn, Syntax, Syntax, Locked, Locked, Other, Other
+        ]);
+    }
+
+    #[test]
+    fn constraints() {
+        assert!(SqliteErrorKind::UniqueConstraint.is_constraint());
+        assert!(SqliteErrorKind::Constraint.is_constraint());
+        assert!(!SqliteErrorKind::NoSuchTable.is_constraint());
+        assert!(!SqliteErrorKind::Other.is_constraint());
+    }
+
+    #[test]
+    fn sqlite_kind_of_errors() {
+        // batches prefix the message with the failed statement
+        let error = RqliteError::SqlError("statement 2 failed: UNIQUE constraint failed: foo.id".to_owned());
+        assert_eq!(error.sqlite_kind(), Some(SqliteErrorKind::UniqueConstraint));
+        assert_eq!(RqliteError::SqlError("no such table: bar".to_owned()).sqlite_kind(), Some(SqliteErrorKind::NoSuchTable));
+        assert_eq!(RqliteError::NoLeader.sqlite_kind(), None);
+    }
+}
+
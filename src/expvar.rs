@@ -0,0 +1,106 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// Metrics exposed by rqlite on `/debug/vars`
+///
+/// See [`Connection::expvars()`](crate::Connection::expvars)
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ExpVars {
+    /// Command line rqlited was started with
+    #[serde(default)]
+    pub cmdline: Vec<String>,
+    /// Go runtime memory statistics
+    #[serde(default)]
+    pub memstats: MemStats,
+    /// HTTP service counters, like `queries` or `executions`
+    #[serde(default)]
+    pub http: Map<String, Value>,
+    /// Store counters, like `leader_changes_observed` or `snapshot_created`
+    #[serde(default)]
+    pub store: Map<String, Value>,
+    /// Database counters
+    #[serde(default)]
+    pub db: Map<String, Value>,
+    /// Every other variable, keyed by name
+    #[serde(flatten)]
+    pub other: Map<String, Value>
+}
+
+/// Go runtime memory statistics, in bytes unless noted
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct MemStats {
+    /// Bytes of allocated heap objects
+    pub alloc: u64,
+    /// Cumulative bytes allocated for heap objects
+    pub total_alloc: u64,
+    /// Total bytes of memory obtained from the OS
+    pub sys: u64,
+    /// Cumulative count of heap objects allocated
+    pub mallocs: u64,
+    /// Cumulative count of heap objects freed
+    pub frees: u64,
+    /// Bytes of allocated heap objects
+    pub heap_alloc: u64,
+    /// Bytes of heap memory obtained from the OS
+    pub heap_sys: u64,
+    /// Bytes in idle spans
+    pub heap_idle: u64,
+    /// Bytes in in-use spans
+    pub heap_inuse: u64,
+    /// Number of allocated heap objects
+    pub heap_objects: u64,
+    /// Number of completed GC cycles
+    #[serde(rename = "NumGC")]
+    pub num_gc: u32,
+    /// Cumulative nanoseconds in GC stop-the-world pauses
+    pub pause_total_ns: u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `/debug/vars` of rqlite v8, trimmed
+    const EXPVARS: &str = r#"{
+        "cmdline": ["rqlited", "-node-id", "1", "-http-addr", "localhost:4001", "/var/lib/rqlite"],
+        "db": {"execute_transactions": 2, "execution_errors": 1, "executions": 5, "queries": 3, "query_errors": 0},
+        "http": {"backups": 0, "executions": 4, "queries": 3, "queued_executions": 0, "readyz": 12},
+        "memstats": {
+            "Alloc": 3421808, "TotalAlloc": 5765368, "Sys": 16270344, "Lookups": 0, "Mallocs": 30140, "Frees": 17452,
+            "HeapAlloc": 3421808, "HeapSys": 7667712, "HeapIdle": 2457600, "HeapInuse": 5210112, "HeapReleased": 2072576,
+            "HeapObjects": 12688, "StackInuse": 655360, "PauseTotalNs": 423584, "NumGC": 3, "NumForcedGC": 0,
+            "GCCPUFraction": 0.0000132, "EnableGC": true, "DebugGC": false, "BySize": [{"Size": 8, "Mallocs": 1404, "Frees": 1026}]
+        },
+        "mux": {"num_connections_handled": 3, "num_unregistered_handlers": 0},
+        "store": {"leader_changes_observed": 1, "snapshot_created": 0, "num_backups": 0}
+    }"#;
+
+    #[test]
+    fn known_variables() {
+        let vars: ExpVars = serde_json::from_str(EXPVARS).unwrap();
+        assert_eq!(vars.cmdline[0], "rqlited");
+        assert_eq!(vars.memstats.alloc, 3421808);
+        assert_eq!(vars.memstats.heap_inuse, 5210112);
+        assert_eq!(vars.memstats.heap_objects, 12688);
+        assert_eq!(vars.memstats.num_gc, 3);
+        assert_eq!(vars.memstats.pause_total_ns, 423584);
+        assert_eq!(vars.http["queries"], 3);
+        assert_eq!(vars.store["leader_changes_observed"], 1);
+        assert_eq!(vars.db["execution_errors"], 1);
+    }
+
+    #[test]
+    fn other_variables() {
+        let vars: ExpVars = serde_json::from_str(EXPVARS).unwrap();
+        assert_eq!(vars.other.keys().collect::<Vec<_>>(), ["mux"]);
+        assert_eq!(vars.other["mux"]["num_connections_handled"], 3);
+    }
+
+    #[test]
+    fn missing_variables() {
+        let vars: ExpVars = serde_json::from_str(r#"{"cmdline": ["rqlited"]}"#).unwrap();
+        assert_eq!(vars.memstats.heap_alloc, 0);
+        assert!(vars.http.is_empty() && vars.store.is_empty() && vars.other.is_empty());
+    }
+}
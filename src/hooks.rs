@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use std::time::Duration;
+use crate::connect::ConnectOptions;
+
+/// Statement sent to rqlite, passed to the [`ConnectOptions::on_query()`]
+/// and [`ConnectOptions::on_slow_query()`] callbacks
+#[derive(Clone, Debug)]
+pub struct QueryInfo<'a> {
+    /// Sql of the statement
+    pub sql: &'a str,
+    /// Parameters of the statement, None unless enabled with [`ConnectOptions::log_query_params()`]
+    pub params: Option<&'a [serde_json::Value]>,
+    /// Time taken by the request the statement was sent in, retries included
+    pub duration: Duration,
+    /// Error of the statement or of its request, None on success
+    pub error: Option<&'a str>
+}
+
+/// Callback receiving statements
+#[derive(Clone)]
+pub(crate) struct QueryHook(pub(crate) Arc<dyn Fn(&QueryInfo) + Send + Sync>);
+
+impl std::fmt::Debug for QueryHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("QueryHook")
+    }
+}
+
+/// Check if a callback wants statements of a request that took `duration`
+pub(crate) fn wants(options: &ConnectOptions, duration: Duration) -> bool {
+    options.on_query.is_some() || slow_hook(options, duration).is_some()
+}
+
+fn slow_hook(options: &ConnectOptions, duration: Duration) -> Option<&QueryHook> {
+    match (&options.on_slow_query, options.slow_query_threshold) {
+        (Some(hook), Some(threshold)) if duration > threshold => Some(hook),
+        _ => None
+    }
+}
+
+/// Pass every statement of `statements` to the query callback, and to the slow query
+/// callback if the request took longer than the threshold
+///
+/// `errors` holds the error of each statement, or a single error for the whole request
+pub(crate) fn on_query(options: &ConnectOptions, statements: &serde_json::Value, duration: Duration, errors: &[Option<String>]) {
+    let hooks: Vec<&QueryHook> = options.on_query.iter().chain(slow_hook(options, duration)).collect();
+    if hooks.is_empty() {
+        return;
+    }
+    for (i, statement) in statements.as_array().into_iter().flatten().enumerate() {
+        let (sql, params) = match statement {
+            serde_json::Value::Array(v) => match v.split_first() {
+                Some((sql, params)) => (sql.as_str().unwrap_or_default(), params),
+                None => continue
+            },
+            v => (v.as_str().unwrap_or_default(), &[][..])
+        };
+        let error = match errors {
+            [error] => error.as_deref(),
+            errors => errors.get(i).and_then(|e| e.as_deref())
+        };
+        let info = QueryInfo {
+            sql,
+            params: if options.log_query_params { Some(params) } else { None },
+            duration,
+            error
+        };
+        for hook in &hooks {
+            (hook.0)(&info);
+        }
+    }
+}
@@ -1,25 +1,106 @@
 //! An asynchronous client library for rqlite.
 //!
 //! This library uses tokio for sockets and hyper to handle http requests.
-//! 
-//! Currently there is no transaction support.
-//! ```
-//! use rqlite::ConnectOptions;
 //!
-//! let mut conn = ConnectOptions::new("my.node.local", 4001)
+//! Tls is handled by native-tls, or by rustls when the `rustls` feature is enabled
+//! (disable default features to drop native-tls). The `socks5` feature adds socks5 proxy support,
+//! the `tracing` feature creates spans for queries and requests and the `metrics` feature
+//! emits request and query metrics through the `metrics` facade. With the `tower` feature
+//! a [`Connection`] is a `tower::Service` sending http requests to the node. The `deadpool`
+//! and `bb8` features add connection managers for these pools, and the `blocking` feature adds
+//! a blocking connection for code that is not async. The `cli` feature builds the
+//! `rqlite-migrate` binary applying the migrations of a directory, see [`migrate`].
+//! The `chrono` and `time` features add date and time parameters and columns, the `uuid`
+//! and `decimal` features add `Uuid` and `Decimal` ones. With the `gzip` feature responses
+//! are requested gzipped and decompressed as they arrive, the `simd-json` feature parses
+//! responses with simd-json.
+//!
+//! Multiple statements can be applied atomically using [`Connection::transaction()`].
+//! ```no_run
+//! # use rqlite::*;
+//! # type Error = Box<dyn std::error::Error + Send + Sync>;
+//! # async fn run() -> Result<(), Error> {
+//! use rqlite::{par, ConnectOptions, Scheme};
+//!
+//! let conn = ConnectOptions::new("my.node.local", 4001)
 //!     .scheme(Scheme::HTTPS)
 //!     .user("root")
 //!     .pass("root")
-//!		.connect().await?;
-//!	conn.execute("SELECT * FROM foo where id = ?;", par!(1)).await?;
+//!     .connect().await?;
+//! conn.execute("INSERT INTO foo(name) VALUES (?);", par!("fiona")).await?;
+//! conn.query("SELECT * FROM foo where id = ?;", par!(1)).await?;
+//! # Ok(())
+//! # }
 //! ```
+//!
+//! Parameters can also be passed as tuples or arrays, see [`IntoParams`].
 
+mod auth;
+#[cfg(feature = "bb8")]
+pub mod bb8;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod client;
+mod cluster;
+mod compress;
+mod config;
 mod connect;
+mod convert;
 mod cursor;
+mod datetime;
+#[cfg(feature = "deadpool")]
+pub mod deadpool;
+mod env;
+mod expvar;
+mod hooks;
+pub mod migrate;
+mod middleware;
+mod options;
+mod paginate;
+mod pool;
+mod proxy;
+mod query;
+mod retry;
 mod row;
+mod schema;
+mod sql;
+mod statement;
+mod stats;
+mod stream;
+mod tls;
+mod trace;
+mod transport;
 mod types;
+mod url;
+mod watch;
 mod error;
 
-pub use connect::{Node, Scheme, ConnectOptions, Connection};
-pub use serde_json::{Value, to_value};
-pub use error::RqliteError;
+pub use connect::{Node, Scheme, ConnectOptions, Connection, Socket};
+pub use client::Client;
+pub use cluster::ClusterConnection;
+pub use cursor::{Cursor, Rows, StatementError, StatementResult};
+pub use expvar::{ExpVars, MemStats};
+pub use hooks::QueryInfo;
+pub use middleware::{BoxFuture, Middleware, Next};
+pub use transport::Transport;
+pub use paginate::Paginator;
+pub use options::{BackupFormat, BackupOptions, Level, QueryOptions, ReadyOptions, RequestOptions};
+pub use pool::{Pool, PoolOptions, PoolConnection};
+pub use query::{Query, TypedQuery};
+pub use retry::{ErrorClass, RetryPolicy};
+pub use serde_json::{Value, Map, to_value};
+pub use hyper;
+pub use error::{RqliteError, SqliteErrorKind};
+pub use row::{Row, FromRow};
+pub use statement::Statement;
+pub use stream::RowStream;
+pub use schema::{Column, Index, Table};
+pub use types::{IntoParams, Json, Params, ToParam, Type};
+pub use watch::{ClusterEvent, ClusterWatcher};
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::types::{CustomParam, Param, SerializeParam};
+    pub use serde_json::Error as JsonError;
+}
+#[cfg(feature = "derive")]
+pub use rqlite_derive::{FromRow, include_migrations, query};
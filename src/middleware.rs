@@ -0,0 +1,91 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use hyper::{Body, Request, Response};
+use crate::connect::Connection;
+use crate::error::RqliteError;
+
+/// Boxed future returned by middlewares
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Layer wrapped around every http request sent by a connection
+///
+/// A middleware can change the request, call the rest of the chain with [`Next::run()`]
+/// (or not, to answer from a cache), and inspect the response.
+/// Middlewares run in the order they were added, around redirects and retries done by the client.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// use rqlite::{BoxFuture, Middleware, Next, RqliteError};
+/// use rqlite::hyper::{Body, Request, Response};
+///
+/// struct Logger;
+///
+/// impl Middleware for Logger {
+///     fn handle<'a>(&'a self, req: Request<Body>, next: Next<'a>) -> BoxFuture<'a, Result<Response<Body>, RqliteError>> {
+///         Box::pin(async move {
+///             let uri = req.uri().clone();
+///             let resp = next.run(req).await;
+///             println!("{} -> {:?}", uri, resp.as_ref().map(|r| r.status()));
+///             resp
+///         })
+///     }
+/// }
+///
+/// let conn = ConnectOptions::new("my.node.local", 4001)
+///        .middleware(Logger)
+///        .connect().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Middleware: Send + Sync + 'static {
+    fn handle<'a>(&'a self, req: Request<Body>, next: Next<'a>) -> BoxFuture<'a, Result<Response<Body>, RqliteError>>;
+}
+
+/// Rest of the middleware chain, ending with the node
+pub struct Next<'a> {
+    connection: &'a Connection,
+    middlewares: &'a [Arc<dyn Middleware>]
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(connection: &'a Connection, middlewares: &'a [Arc<dyn Middleware>]) -> Next<'a> {
+        Next { connection, middlewares }
+    }
+
+    /// Pass `req` to the next middleware, or send it to the node
+    pub fn run(self, req: Request<Body>) -> BoxFuture<'a, Result<Response<Body>, RqliteError>> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => middleware.handle(req, Next::new(self.connection, rest)),
+            None => Box::pin(self.connection.send_traced(req))
+        }
+    }
+}
+
+/// Middlewares of a connection
+#[derive(Clone, Default)]
+pub(crate) struct Middlewares(pub(crate) Vec<Arc<dyn Middleware>>);
+
+impl std::fmt::Debug for Middlewares {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Middlewares({})", self.0.len())
+    }
+}
+
+/// Connections can be wrapped in tower layers, each call sends one request to the current node
+#[cfg(feature = "tower")]
+impl tower_service::Service<Request<Body>> for Connection {
+    type Response = Response<Body>;
+    type Error = RqliteError;
+    type Future = BoxFuture<'static, Result<Response<Body>, RqliteError>>;
+
+    fn poll_ready(&mut self, _cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let connection = self.clone();
+        Box::pin(async move { connection.send_request(connection.authorize(req)).await })
+    }
+}
@@ -0,0 +1,327 @@
+//! Schema migrations
+//!
+//! Applied versions are recorded in the `_rqlite_migrations` table, every migration is applied
+//! in a transaction along with its record.
+//! ```no_run
+//! # use rqlite::*;
+//! # type Error = Box<dyn std::error::Error + Send + Sync>;
+//! # async fn run() -> Result<(), Error> {
+//! # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+//! use rqlite::migrate::Migrator;
+//!
+//! Migrator::new()
+//!     .add("001_init.sql", "CREATE TABLE foo(id INTEGER PRIMARY KEY, name TEXT)")
+//!     .add("002_age.sql", "ALTER TABLE foo ADD COLUMN age INTEGER")
+//!     .run(&conn).await?;
+//!
+//! // or, with the `derive` feature, embed every .sql file of a directory
+//! // rqlite::include_migrations!("./migrations").run(&conn).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::connect::Connection;
+use crate::error::RqliteError;
+use crate::sql::{tokens, Token};
+
+/// Table recording applied migrations
+pub const MIGRATIONS_TABLE: &str = "_rqlite_migrations";
+
+/// Versioned sql script
+#[derive(Clone, Debug)]
+pub struct Migration {
+    /// Unique version, usually the file name
+    pub version: String,
+    /// Sql applying the migration
+    pub up: String,
+    /// Sql reverting the migration, if it is reversible
+    pub down: Option<String>
+}
+
+/// Ordered list of migrations
+#[derive(Clone, Debug, Default)]
+pub struct Migrator {
+    migrations: Vec<Migration>
+}
+
+impl Migrator {
+    /// Create a migrator without migrations
+    pub fn new() -> Migrator {
+        Migrator::default()
+    }
+
+    /// Add a migration, migrations are applied in the order they are added
+    pub fn add(&mut self, version: &str, sql: &str) -> &mut Migrator {
+        self.migrations.push(Migration { version: version.to_owned(), up: sql.to_owned(), down: None });
+        self
+    }
+
+    /// Add a migration which can be reverted with `down`
+    pub fn add_reversible(&mut self, version: &str, up: &str, down: &str) -> &mut Migrator {
+        self.migrations.push(Migration { version: version.to_owned(), up: up.to_owned(), down: Some(down.to_owned()) });
+        self
+    }
+
+    /// Load the `.sql` files of a directory, in file name order
+    ///
+    /// `name.up.sql` and `name.down.sql` make a reversible migration with version `name.sql`,
+    /// like [`include_migrations!`](crate::include_migrations) but read at runtime.
+    ///
+    /// Returns `RqliteError::Config` if the directory or a file cannot be read
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Migrator, RqliteError> {
+        let dir = dir.as_ref();
+        let read_error = |path: &Path, e: std::io::Error| RqliteError::Config(format!("Cannot read {}: {}", path.display(), e));
+        // version -> (up, down)
+        let mut scripts: BTreeMap<String, (Option<String>, Option<String>)> = BTreeMap::new();
+        for entry in std::fs::read_dir(dir).map_err(|e| read_error(dir, e))? {
+            let path = entry.map_err(|e| read_error(dir, e))?.path();
+            let name = match path.file_name() {
+                Some(v) => v.to_string_lossy().into_owned(),
+                None => continue
+            };
+            let (version, down) = if let Some(stem) = name.strip_suffix(".down.sql") {
+                (format!("{}.sql", stem), true)
+            } else if let Some(stem) = name.strip_suffix(".up.sql") {
+                (format!("{}.sql", stem), false)
+            } else if name.ends_with(".sql") {
+                (name, false)
+            } else {
+                continue;
+            };
+            let sql = std::fs::read_to_string(&path).map_err(|e| read_error(&path, e))?;
+            let script = scripts.entry(version).or_default();
+            if down {
+                script.1 = Some(sql);
+            } else {
+                script.0 = Some(sql);
+            }
+        }
+
+        let mut migrator = Migrator::new();
+        for (version, (up, down)) in scripts {
+            let up = up.ok_or_else(|| RqliteError::Config(format!("Migration {} has no up script", version)))?;
+            migrator.migrations.push(Migration { version, up, down });
+        }
+        Ok(migrator)
+    }
+
+    /// Migrations, in order
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Versions already applied to the database, in the order they were applied
+    ///
+    /// Returns RqliteError if the request fails
+    pub async fn applied(&self, conn: &Connection) -> Result<Vec<String>, RqliteError> {
+        self.init(conn).await?;
+        let rows = conn.fetch_all(&format!("SELECT version FROM {} ORDER BY applied_at, rowid", MIGRATIONS_TABLE), Vec::new()).await?;
+        rows.iter()
+            .map(|row| row.get::<String>(0).map_err(|e| RqliteError::ResultShape(e.to_string())))
+            .collect()
+    }
+
+    /// Migrations not applied yet, in order
+    ///
+    /// Returns RqliteError if the request fails
+    pub async fn pending(&self, conn: &Connection) -> Result<Vec<&Migration>, RqliteError> {
+        let applied = self.applied(conn).await?;
+        Ok(self.migrations.iter().filter(|m| !applied.contains(&m.version)).collect())
+    }
+
+    /// Apply pending migrations in order, returns the versions applied
+    ///
+    /// Each migration is applied in its own transaction, a failing migration stops the run.
+    ///
+    /// Returns RqliteError if the request fails, `RqliteError::SqlError` if a migration fails
+    pub async fn run(&self, conn: &Connection) -> Result<Vec<String>, RqliteError> {
+        let mut applied = Vec::new();
+        for migration in self.pending(conn).await? {
+            let record = format!("INSERT INTO {}(version, applied_at) VALUES (?, ?)", MIGRATIONS_TABLE);
+            self.apply(conn, &migration.version, &migration.up, (&record, vec![migration.version.clone().into(), now().into()])).await?;
+            applied.push(migration.version.clone());
+        }
+        Ok(applied)
+    }
+
+    /// Revert the last applied migration with its `down` script, returns its version
+    ///
+    /// Returns None if no migration is applied.
+    ///
+    /// Returns RqliteError if the request fails, `RqliteError::Config` if the migration is unknown
+    /// or not reversible, `RqliteError::SqlError` if the `down` script fails
+    pub async fn revert(&self, conn: &Connection) -> Result<Option<String>, RqliteError> {
+        let version = match self.applied(conn).await?.pop() {
+            Some(v) => v,
+            None => return Ok(None)
+        };
+        let down = match self.migrations.iter().find(|m| m.version == version) {
+            Some(Migration { down: Some(down), .. }) => down,
+            Some(_) => return Err(RqliteError::Config(format!("Migration {} is not reversible", version))),
+            None => return Err(RqliteError::Config(format!("Unknown migration {}", version)))
+        };
+        let record = format!("DELETE FROM {} WHERE version = ?", MIGRATIONS_TABLE);
+        self.apply(conn, &version, down, (&record, vec![version.clone().into()])).await?;
+        Ok(Some(version))
+    }
+
+    /// Create the migrations table if needed
+    async fn init(&self, conn: &Connection) -> Result<(), RqliteError> {
+        let sql = format!("CREATE TABLE IF NOT EXISTS {} (version TEXT PRIMARY KEY, applied_at INTEGER NOT NULL)", MIGRATIONS_TABLE);
+        conn.execute(&sql, Vec::new()).await?;
+        Ok(())
+    }
+
+    /// Run statements of `sql` and the `record` statement in a transaction
+    async fn apply(&self, conn: &Connection, version: &str, sql: &str, record: (&str, Vec<serde_json::Value>)) -> Result<(), RqliteError> {
+        let statements = split_statements(sql);
+        let mut queries: Vec<(&str, Vec<serde_json::Value>)> = statements.iter().map(|s| (s.as_str(), Vec::new())).collect();
+        queries.push(record);
+        match conn.transaction(&queries).await {
+            Ok(_) => Ok(()),
+            Err(e) => match e {
+                RqliteError::SqlError(message) => Err(RqliteError::SqlError(format!("Migration {} failed: {}", version, message))),
+                e => Err(e)
+            }
+        }
+    }
+}
+
+/// Unix timestamp in seconds
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Split a sql script into statements
+///
+/// Semicolons in strings, quoted identifiers, comments and trigger bodies don't end a statement.
+pub(crate) fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    for token in tokens(sql) {
+        match token {
+            Token::Comment(comment) if comment.starts_with("--") => (),
+            Token::Comment(_) => current.push(' '),
+            Token::Text(text) => {
+                for c in text.chars() {
+                    if c == ';' && !in_trigger(&current) {
+                        push_statement(&mut statements, &current);
+                        current.clear();
+                    } else {
+                        current.push(c);
+                    }
+                }
+            },
+            Token::Quoted(text) | Token::Param(text) | Token::Named(text) => current.push_str(text)
+        }
+    }
+    push_statement(&mut statements, &current);
+    statements
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    let statement = statement.trim();
+    if !statement.is_empty() {
+        statements.push(statement.to_owned());
+    }
+}
+
+/// Check if `statement` is a trigger whose body is not finished yet
+///
+/// The body ends at the `END` matching its `BEGIN`, `CASE ... END` expressions are nested in it.
+fn in_trigger(statement: &str) -> bool {
+    let words: Vec<String> = tokens(statement)
+        .filter_map(|token| match token {
+            Token::Text(text) => Some(text),
+            _ => None
+        })
+        .flat_map(|text| text.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$')))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_uppercase())
+        .collect();
+    let is_trigger = match words.as_slice() {
+        [create, trigger, ..] if create == "CREATE" && trigger == "TRIGGER" => true,
+        [create, temp, trigger, ..] if create == "CREATE" && (temp == "TEMP" || temp == "TEMPORARY") && trigger == "TRIGGER" => true,
+        _ => false
+    };
+    if !is_trigger {
+        return false;
+    }
+    let mut body = false;
+    let mut depth = 0;
+    for word in &words {
+        match word.as_str() {
+            "BEGIN" => {
+                body = true;
+                depth += 1;
+            },
+            "CASE" => depth += 1,
+            "END" => depth -= 1,
+            _ => ()
+        }
+    }
+    !body || depth > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements_are_split_on_semicolons() {
+        assert_eq!(split_statements("CREATE TABLE a(id INTEGER);\n\nCREATE TABLE b(id INTEGER);\n"),
+                vec!["CREATE TABLE a(id INTEGER)", "CREATE TABLE b(id INTEGER)"]);
+    }
+
+    #[test]
+    fn trailing_statement_without_semicolon() {
+        assert_eq!(split_statements("DELETE FROM a; DELETE FROM b"), vec!["DELETE FROM a", "DELETE FROM b"]);
+        assert_eq!(split_statements(";;  \n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn semicolons_in_strings() {
+        assert_eq!(split_statements("INSERT INTO a VALUES ('x;y', 'it''s;'); INSERT INTO \"b;\" VALUES (1)"),
+                vec!["INSERT INTO a VALUES ('x;y', 'it''s;')", "INSERT INTO \"b;\" VALUES (1)"]);
+        assert_eq!(split_statements("SELECT [a;b], `c;d`"), vec!["SELECT [a;b], `c;d`"]);
+    }
+
+    #[test]
+    fn comments_are_removed() {
+        assert_eq!(split_statements("-- first; table\nCREATE TABLE a(id INTEGER); /* ; */ DELETE FROM a -- done;"),
+                vec!["CREATE TABLE a(id INTEGER)", "DELETE FROM a"]);
+        assert_eq!(split_statements("SELECT 1/*;*/+ 2"), vec!["SELECT 1 + 2"]);
+    }
+
+    #[test]
+    fn trigger_bodies_are_kept() {
+        let sql = "CREATE TRIGGER t AFTER INSERT ON a BEGIN\n  INSERT INTO b VALUES (new.id);\n  DELETE FROM c;\nEND;\nDELETE FROM a;";
+        assert_eq!(split_statements(sql), vec![
+            "CREATE TRIGGER t AFTER INSERT ON a BEGIN\n  INSERT INTO b VALUES (new.id);\n  DELETE FROM c;\nEND",
+            "DELETE FROM a"
+        ]);
+        let sql = "create temp trigger t after delete on a begin delete from b; end; delete from a";
+        assert_eq!(split_statements(sql).len(), 2);
+    }
+
+    #[test]
+    fn case_in_trigger_bodies() {
+        let trigger = "CREATE TRIGGER t AFTER UPDATE ON a WHEN CASE WHEN new.x THEN 1 END BEGIN\n  \
+                UPDATE b SET y = CASE WHEN new.x > 0 THEN 'end' ELSE 0 END;\n  \
+                UPDATE c SET z = CASE new.x WHEN 1 THEN 2 END;\nEND";
+        let sql = format!("{};\nDELETE FROM a;", trigger);
+        assert_eq!(split_statements(&sql), vec![trigger, "DELETE FROM a"]);
+    }
+
+    #[test]
+    fn end_in_strings_and_comments() {
+        let sql = "CREATE TRIGGER t AFTER INSERT ON a BEGIN INSERT INTO b VALUES ('END'); \
+                INSERT INTO \"end\" VALUES (1); /* END; */ END; -- END;\nDELETE FROM a";
+        assert_eq!(split_statements(sql), vec![
+            "CREATE TRIGGER t AFTER INSERT ON a BEGIN INSERT INTO b VALUES ('END'); INSERT INTO \"end\" VALUES (1);   END",
+            "DELETE FROM a"
+        ]);
+    }
+}
@@ -0,0 +1,368 @@
+use std::str::FromStr;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::auth::Auth;
+use crate::error::RqliteError;
+
+/// Read consistency level
+///
+/// See <https://rqlite.io/docs/api/read-consistency/>
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// Read from the local database of the node, without checking leadership
+    None,
+    /// Leader checks it is still leader before reading (rqlite default)
+    Weak,
+    /// Read goes through the raft log
+    Strong,
+    /// Leader confirms leadership with a quorum before reading
+    Linearizable,
+    /// `None` on read-only nodes, `Weak` otherwise
+    Auto
+}
+
+impl FromStr for Level {
+    type Err = RqliteError;
+
+    /// Parse level from its rqlite name, like `weak`
+    fn from_str(s: &str) -> Result<Level, RqliteError> {
+        Ok(match s {
+            "none"         => Level::None,
+            "weak"         => Level::Weak,
+            "strong"       => Level::Strong,
+            "linearizable" => Level::Linearizable,
+            "auto"         => Level::Auto,
+            _ => return Err(RqliteError::Config(format!("Unknown consistency level {}", s)))
+        })
+    }
+}
+
+impl Level {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Level::None         => "none",
+            Level::Weak         => "weak",
+            Level::Strong       => "strong",
+            Level::Linearizable => "linearizable",
+            Level::Auto         => "auto"
+        }
+    }
+}
+
+/// Options for a single query
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// use rqlite::{QueryOptions, Level};
+///
+/// let mut cur = conn.cursor();
+/// cur.query_with("SELECT * FROM foo", par!(), QueryOptions::new().level(Level::Strong)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct QueryOptions {
+    pub(crate) level: Option<Level>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) associative: bool,
+    pub(crate) freshness: Option<Duration>,
+    pub(crate) freshness_strict: bool,
+    pub(crate) db_timeout: Option<Duration>
+}
+
+impl QueryOptions {
+    /// Create query options, rqlite defaults are used for unset options
+    pub fn new() -> QueryOptions {
+        QueryOptions::default()
+    }
+
+    /// Set read consistency level
+    pub fn level(&mut self, level: Level) -> &mut QueryOptions {
+        self.level = Some(level);
+        self
+    }
+
+    /// Set request timeout, overriding [`ConnectOptions::request_timeout()`](crate::ConnectOptions::request_timeout)
+    pub fn timeout(&mut self, timeout: Duration) -> &mut QueryOptions {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Abort the query on the node when it runs longer than `db_timeout`
+    ///
+    /// Unlike [`QueryOptions::timeout()`], which only stops waiting for the response,
+    /// the statement is interrupted by rqlite and reported as a `RqliteError::SqlError`.
+    pub fn db_timeout(&mut self, db_timeout: Duration) -> &mut QueryOptions {
+        self.db_timeout = Some(db_timeout);
+        self
+    }
+
+    /// Maximum age of the node data for reads with [`Level::None`]
+    ///
+    /// Reads from a node that has not heard from the leader within `freshness`
+    /// fail with `RqliteError::StaleRead`.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # use std::time::Duration;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// conn.query_with("SELECT * FROM foo", par!(), QueryOptions::new().level(Level::None).freshness(Duration::from_secs(1))).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn freshness(&mut self, freshness: Duration) -> &mut QueryOptions {
+        self.freshness = Some(freshness);
+        self
+    }
+
+    /// Also fail reads when the data itself is older than the freshness
+    ///
+    /// Without it, a node in contact with the leader serves its data even if the last
+    /// write it applied is older than [`QueryOptions::freshness()`]. Ignored without freshness.
+    pub fn freshness_strict(&mut self, freshness_strict: bool) -> &mut QueryOptions {
+        self.freshness_strict = freshness_strict;
+        self
+    }
+
+    /// Request rows as maps of column names to values (`associative` format)
+    ///
+    /// Rows are read the same way, but columns are ordered by name as rqlite does not keep their order.
+    pub fn associative(&mut self, associative: bool) -> &mut QueryOptions {
+        self.associative = associative;
+        self
+    }
+
+    /// Url query string for these options, empty or starting with `?`
+    pub(crate) fn query_string(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(level) = self.level {
+            params.push(format!("level={}", level.as_str()));
+        }
+        if let Some(freshness) = self.freshness {
+            params.push(format!("freshness={}", go_duration(freshness)));
+            if self.freshness_strict {
+                params.push("freshness_strict".to_owned());
+            }
+        }
+        if let Some(db_timeout) = self.db_timeout {
+            params.push(format!("db_timeout={}", go_duration(db_timeout)));
+        }
+        if self.associative {
+            params.push("associative".to_owned());
+        }
+        if params.is_empty() {
+            return String::new();
+        }
+        format!("?{}", params.join("&"))
+    }
+}
+
+/// Duration in the format parsed by Go, like `1500ms`
+fn go_duration(duration: Duration) -> String {
+    if duration.subsec_nanos() % 1_000_000 == 0 {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{}ns", duration.as_nanos())
+    }
+}
+
+/// Format of a database backup
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupFormat {
+    /// SQLite database file (rqlite default)
+    Binary,
+    /// SQL text dump
+    Sql
+}
+
+/// Options for a database backup
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// use rqlite::{BackupFormat, BackupOptions};
+///
+/// let file = tokio::fs::File::create("dump.sql").await?;
+/// conn.backup_with(file, BackupOptions::new().fmt(BackupFormat::Sql)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct BackupOptions {
+    pub(crate) fmt: BackupFormat,
+    pub(crate) vacuum: bool,
+    pub(crate) compress: bool
+}
+
+impl Default for BackupOptions {
+    fn default() -> BackupOptions {
+        BackupOptions { fmt: BackupFormat::Binary, vacuum: false, compress: false }
+    }
+}
+
+impl BackupOptions {
+    /// Create backup options, binary format without vacuum or compression
+    pub fn new() -> BackupOptions {
+        BackupOptions::default()
+    }
+
+    /// Set backup format
+    pub fn fmt(&mut self, fmt: BackupFormat) -> &mut BackupOptions {
+        self.fmt = fmt;
+        self
+    }
+
+    /// Vacuum the database copy before sending it, binary format only
+    pub fn vacuum(&mut self, vacuum: bool) -> &mut BackupOptions {
+        self.vacuum = vacuum;
+        self
+    }
+
+    /// Gzip the backup, the compressed data is written as is
+    pub fn compress(&mut self, compress: bool) -> &mut BackupOptions {
+        self.compress = compress;
+        self
+    }
+
+    /// Url query string for these options, empty or starting with `?`
+    pub(crate) fn query_string(&self) -> String {
+        let mut params = Vec::new();
+        if self.fmt == BackupFormat::Sql {
+            params.push("fmt=sql");
+        }
+        if self.vacuum {
+            params.push("vacuum");
+        }
+        if self.compress {
+            params.push("compress");
+        }
+        if params.is_empty() {
+            return String::new();
+        }
+        format!("?{}", params.join("&"))
+    }
+}
+
+/// Options applied to every request of a connection, see [`Connection::with_options()`](crate::Connection::with_options)
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// # let admin_pass = String::new();
+/// use rqlite::RequestOptions;
+///
+/// // queries use a limited user, removing a node needs the admin
+/// conn.with_options(RequestOptions::new().credentials("admin", &admin_pass))
+///     .remove("node3").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RequestOptions {
+    /// Authorization header value replacing the connection credentials
+    pub(crate) authorization: Option<String>,
+    pub(crate) timings: bool,
+    pub(crate) raft_index: bool
+}
+
+impl RequestOptions {
+    /// Create request options, using the connection settings
+    pub fn new() -> RequestOptions {
+        RequestOptions::default()
+    }
+
+    /// Authenticate with these basic auth credentials instead of the connection ones
+    pub fn credentials(&mut self, user: &str, pass: &str) -> &mut RequestOptions {
+        self.authorization = Some(Auth::basic_header(user, pass));
+        self
+    }
+
+    /// Authenticate with this bearer token instead of the connection credentials
+    pub fn bearer_token(&mut self, token: &str) -> &mut RequestOptions {
+        self.authorization = Some(format!("Bearer {}", token));
+        self
+    }
+
+    /// Ask rqlite for the execution time of statements, read with [`Cursor::timing()`](crate::Cursor::timing)
+    pub fn timings(&mut self, timings: bool) -> &mut RequestOptions {
+        self.timings = timings;
+        self
+    }
+
+    /// Ask rqlite for the raft log index of writes, read with [`Cursor::raft_index()`](crate::Cursor::raft_index)
+    pub fn raft_index(&mut self, raft_index: bool) -> &mut RequestOptions {
+        self.raft_index = raft_index;
+        self
+    }
+
+    /// Parameters added to the url of statement requests
+    pub(crate) fn statement_params(&self) -> Vec<&'static str> {
+        let mut params = Vec::new();
+        if self.timings {
+            params.push("timings");
+        }
+        if self.raft_index {
+            params.push("raft_index");
+        }
+        params
+    }
+}
+
+/// Options for a readiness check
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// use rqlite::ReadyOptions;
+///
+/// // node is up, even if there is no leader
+/// conn.ready_with(ReadyOptions::new().noleader(true)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ReadyOptions {
+    pub(crate) noleader: bool,
+    pub(crate) sync: bool
+}
+
+impl ReadyOptions {
+    /// Create readiness options, node and leader must be ready
+    pub fn new() -> ReadyOptions {
+        ReadyOptions::default()
+    }
+
+    /// Only check the node itself, not that it is in contact with a leader
+    pub fn noleader(&mut self, noleader: bool) -> &mut ReadyOptions {
+        self.noleader = noleader;
+        self
+    }
+
+    /// Wait until the node has applied every log entry committed by the leader
+    pub fn sync(&mut self, sync: bool) -> &mut ReadyOptions {
+        self.sync = sync;
+        self
+    }
+
+    /// Url query string for these options, empty or starting with `?`
+    pub(crate) fn query_string(&self) -> String {
+        let mut params = Vec::new();
+        if self.noleader {
+            params.push("noleader");
+        }
+        if self.sync {
+            params.push("sync");
+        }
+        if params.is_empty() {
+            return String::new();
+        }
+        format!("?{}", params.join("&"))
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures_core::Stream;
+use serde_json::Value;
+use crate::connect::Connection;
+use crate::error::RqliteError;
+use crate::row::Row;
+use crate::types::IntoParams;
+
+type Page = Pin<Box<dyn Future<Output = Result<Vec<Row>, RqliteError>> + Send>>;
+
+/// Rows of a query fetched page by page, see [`Connection::paginate()`]
+///
+/// Each page starts after the key of the last row read, so walking a large table
+/// doesn't scan skipped rows like OFFSET does.
+/// The key must be a unique and non NULL column of the query, like `id` or `rowid`.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// use futures::StreamExt;
+///
+/// let mut rows = conn.paginate("SELECT rowid, name FROM foo WHERE age > ?", par!(18), "rowid");
+/// rows.page_size(500);
+/// while let Some(row) = rows.next().await {
+///     let name: String = row?.get(1)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Paginator {
+    conn: Connection,
+    query: String,
+    params: Vec<Value>,
+    /// Error converting parameters, returned by the first page
+    error: Option<RqliteError>,
+    key: String,
+    page_size: u64,
+    /// Key of the last row read
+    last: Option<Value>,
+    done: bool,
+    rows: VecDeque<Row>,
+    pending: Option<Page>
+}
+
+impl std::fmt::Debug for Paginator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Paginator")
+            .field("query", &self.query)
+            .field("key", &self.key)
+            .field("page_size", &self.page_size)
+            .field("last", &self.last)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl Paginator {
+    fn new(conn: &Connection, query: &str, params: impl IntoParams, key: &str) -> Paginator {
+        let (params, error) = match crate::types::into_params(params) {
+            Ok(params) => (params, None),
+            Err(e) => (Vec::new(), Some(e))
+        };
+        Paginator {
+            conn: conn.clone(),
+            query: query.to_owned(),
+            params,
+            error,
+            key: key.to_owned(),
+            page_size: 1000,
+            last: None,
+            done: false,
+            rows: VecDeque::new(),
+            pending: None
+        }
+    }
+
+    /// Set number of rows fetched per request (default 1000)
+    pub fn page_size(&mut self, size: u64) -> &mut Paginator {
+        self.page_size = size.max(1);
+        self
+    }
+
+    /// Sql of the next page, the last key is its last parameter
+    fn sql(&self) -> String {
+        let after = if self.last.is_some() { format!(" WHERE {} > ?", self.key) } else { String::new() };
+        format!("SELECT * FROM ({}){} ORDER BY {} LIMIT {}", self.query, after, self.key, self.page_size)
+    }
+
+    /// Request of the next page
+    fn fetch(&mut self) -> Page {
+        let conn = self.conn.clone();
+        let sql = self.sql();
+        let mut params = self.params.clone();
+        params.extend(self.last.clone());
+        let error = self.error.take();
+        Box::pin(async move {
+            if let Some(e) = error {
+                return Err(e);
+            }
+            conn.fetch_all(&sql, params).await
+        })
+    }
+
+    /// Remember the key of the last row of `page`
+    fn advance(&mut self, page: &[Row]) -> Result<(), RqliteError> {
+        if (page.len() as u64) < self.page_size {
+            self.done = true;
+        }
+        let row = match page.last() {
+            Some(row) => row,
+            None => return Ok(())
+        };
+        let key = row.columns().iter().position(|c| *c == self.key)
+                .and_then(|id| row.value(id))
+                .filter(|v| !v.is_null());
+        match key {
+            Some(key) => {
+                self.last = Some(key.clone());
+                Ok(())
+            },
+            None => {
+                self.done = true;
+                Err(RqliteError::ResultShape(format!("Page has no {} key, or it is NULL", self.key)))
+            }
+        }
+    }
+
+    /// Fetch the next page, rows already fetched by the stream are returned first
+    ///
+    /// Returns None once all rows have been read
+    pub async fn next_page(&mut self) -> Result<Option<Vec<Row>>, RqliteError> {
+        if !self.rows.is_empty() {
+            return Ok(Some(self.rows.drain(..).collect()));
+        }
+        if self.done {
+            return Ok(None);
+        }
+        let page = match self.pending.take() {
+            Some(page) => page,
+            None => self.fetch()
+        };
+        let rows = match page.await {
+            Ok(v) => v,
+            Err(e) => {
+                self.done = true;
+                return Err(e);
+            }
+        };
+        self.advance(&rows)?;
+        Ok(if rows.is_empty() { None } else { Some(rows) })
+    }
+}
+
+impl Stream for Paginator {
+    type Item = Result<Row, RqliteError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(row) = this.rows.pop_front() {
+                return Poll::Ready(Some(Ok(row)));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            if this.pending.is_none() {
+                this.pending = Some(this.fetch());
+            }
+            let rows = match this.pending.as_mut().map(|page| page.as_mut().poll(cx)) {
+                Some(Poll::Ready(rows)) => rows,
+                _ => return Poll::Pending
+            };
+            this.pending = None;
+            let result = rows.and_then(|rows| {
+                this.advance(&rows)?;
+                Ok(rows)
+            });
+            match result {
+                Ok(rows) => this.rows.extend(rows),
+                Err(e) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+impl Connection {
+    /// Read the rows of a query page by page, ordered by the `key` column
+    ///
+    /// Pages are requested as they are read, see [`Paginator`].
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let mut pages = conn.paginate("SELECT id, name FROM foo", par!(), "id");
+    /// while let Some(page) = pages.next_page().await? {
+    ///     println!("{} rows", page.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn paginate(&self, query: &str, params: impl IntoParams, key: &str) -> Paginator {
+        Paginator::new(self, query, params, key)
+    }
+}
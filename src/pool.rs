@@ -0,0 +1,385 @@
+use std::error::Error;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, OwnedSemaphorePermit};
+use crate::auth::Auth;
+use crate::connect::{ConnectOptions, Connection};
+use crate::error::RqliteError;
+
+/// Longest time between two checks of the idle connections
+const REAP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Pool builder
+#[derive(Clone, Debug)]
+pub struct PoolOptions {
+    connect_options: ConnectOptions,
+    min_connections: usize,
+    max_connections: usize,
+    idle_timeout: Option<Duration>
+}
+
+/// Pool of connections to a rqlite node
+///
+/// Cloning a pool is cheap, every clone shares the same connections.
+#[derive(Clone, Debug)]
+pub struct Pool {
+    inner: Arc<PoolInner>
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    options: PoolOptions,
+    idle: Mutex<Vec<IdleConnection>>,
+    semaphore: Arc<Semaphore>,
+    /// Credentials shared by every connection
+    auth: Arc<Auth>
+}
+
+#[derive(Debug)]
+struct IdleConnection {
+    connection: Connection,
+    since: Instant
+}
+
+/// Connection borrowed from a [`Pool`]
+///
+/// Dereferences to [`Connection`], and is given back to the pool when dropped.
+#[derive(Debug)]
+pub struct PoolConnection {
+    connection: Option<Connection>,
+    pool: Arc<PoolInner>,
+    _permit: OwnedSemaphorePermit
+}
+
+impl PoolOptions {
+    /// Create a new pool builder, connections are created using `options`
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// use rqlite::{ConnectOptions, PoolOptions};
+    ///
+    /// let pool = PoolOptions::new(&ConnectOptions::new("127.0.0.1", 4001))
+    ///        .max_connections(16)
+    ///        .connect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(options: &ConnectOptions) -> PoolOptions {
+        PoolOptions {
+            connect_options: options.clone(),
+            min_connections: 0,
+            max_connections: 10,
+            idle_timeout: Some(Duration::from_secs(600))
+        }
+    }
+
+    /// Set number of connections kept open even when idle (default 0)
+    ///
+    /// Connections found closed are replaced in the background.
+    pub fn min_connections(&mut self, min: usize) -> &mut PoolOptions {
+        self.min_connections = min;
+        self
+    }
+
+    /// Set maximum number of connections open at the same time (default 10)
+    pub fn max_connections(&mut self, max: usize) -> &mut PoolOptions {
+        self.max_connections = max;
+        self
+    }
+
+    /// Set time after which an idle connection is closed (default 10 minutes)
+    ///
+    /// Idle connections are checked in the background at least every 30 seconds,
+    /// and when a connection is given back. Connections are closed down to `min_connections`.
+    /// `None` keeps idle connections open forever.
+    pub fn idle_timeout(&mut self, timeout: Option<Duration>) -> &mut PoolOptions {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Create the pool and open `min_connections` connections
+    ///
+    /// Returns RqliteError on unsuccessful connection or error creating Tls context
+    pub async fn connect(&mut self) -> Result<Pool, RqliteError> {
+        let max_connections = self.max_connections.max(1);
+        let auth = Arc::new(self.connect_options.auth());
+        let mut idle = Vec::with_capacity(self.min_connections);
+        for _ in 0..self.min_connections.min(max_connections) {
+            idle.push(IdleConnection {
+                connection: self.connect_options.connect_with_auth(auth.clone()).await?,
+                since: Instant::now()
+            });
+        }
+
+        let inner = Arc::new(PoolInner {
+            options: self.clone(),
+            idle: Mutex::new(idle),
+            semaphore: Arc::new(Semaphore::new(max_connections)),
+            auth
+        });
+        PoolInner::spawn_reaper(&inner);
+        Ok(Pool { inner })
+    }
+}
+
+impl Pool {
+    /// Get a connection from the pool
+    ///
+    /// Waits if `max_connections` connections are already in use,
+    /// opens a new connection if no idle connection is available.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let pool = PoolOptions::new(&ConnectOptions::new("127.0.0.1", 4001)).connect().await?;
+    /// let conn = pool.acquire().await?;
+    /// conn.execute("INSERT INTO foo(name) VALUES (?)", par!("fiona")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError on unsuccessful connection or error creating Tls context
+    pub async fn acquire(&self) -> Result<PoolConnection, RqliteError> {
+        let permit = match self.inner.semaphore.clone().acquire_owned().await {
+            Ok(v) => v,
+            Err(e) => return Err(RqliteError::Connection(e.to_string()))
+        };
+
+        self.inner.prune();
+        while let Some(idle) = self.inner.pop_idle() {
+            if idle.connection.is_closed().await {
+                continue;
+            }
+            return Ok(PoolConnection {
+                connection: Some(idle.connection),
+                pool: self.inner.clone(),
+                _permit: permit
+            });
+        }
+
+        let connection = self.inner.options.connect_options.connect_with_auth(self.inner.auth.clone()).await?;
+        Ok(PoolConnection {
+            connection: Some(connection),
+            pool: self.inner.clone(),
+            _permit: permit
+        })
+    }
+
+    /// Replace credentials used for basic authentification by every connection
+    ///
+    /// See [`Connection::set_credentials()`]
+    pub fn set_credentials(&self, user: &str, pass: &str) {
+        self.inner.auth.set_header(Auth::basic_header(user, pass));
+    }
+
+    /// Replace the bearer token used by every connection
+    ///
+    /// See [`Connection::set_bearer_token()`]
+    pub fn set_bearer_token(&self, token: &str) {
+        self.inner.auth.set_header(format!("Bearer {}", token));
+    }
+
+    /// Set a callback providing a new bearer token when a request is rejected with 401
+    ///
+    /// See [`Connection::set_token_refresh()`]
+    pub fn set_token_refresh<F, Fut>(&self, refresh: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, Box<dyn Error + Send + Sync>>> + Send + 'static
+    {
+        self.inner.auth.set_refresh(refresh);
+    }
+
+    /// Number of idle connections in the pool
+    pub fn idle(&self) -> usize {
+        self.inner.idle.lock().unwrap().len()
+    }
+
+    /// Number of connections currently borrowed from the pool
+    pub fn in_use(&self) -> usize {
+        self.inner.in_use()
+    }
+}
+
+impl PoolInner {
+    fn pop_idle(&self) -> Option<IdleConnection> {
+        self.idle.lock().unwrap().pop()
+    }
+
+    fn in_use(&self) -> usize {
+        self.options.max_connections.max(1) - self.semaphore.available_permits()
+    }
+
+    /// Close connections idle for longer than `idle_timeout`, oldest first,
+    /// keeping `min_connections` connections open
+    fn prune(&self) {
+        let timeout = match self.options.idle_timeout {
+            Some(v) => v,
+            None => return
+        };
+        let mut idle = match self.idle.lock() {
+            Ok(v) => v,
+            Err(_) => return
+        };
+        let mut open = idle.len() + self.in_use();
+        idle.retain(|conn| {
+            if open > self.options.min_connections && conn.since.elapsed() > timeout {
+                open -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Open idle connections until `min_connections` are open
+    async fn refill(&self) {
+        let min = self.options.min_connections.min(self.options.max_connections.max(1));
+        loop {
+            if self.idle.lock().unwrap().len() + self.in_use() >= min {
+                return;
+            }
+            // retried on the next check
+            let connection = match self.options.connect_options.connect_with_auth(self.auth.clone()).await {
+                Ok(v) => v,
+                Err(_) => return
+            };
+            self.idle.lock().unwrap().push(IdleConnection { connection, since: Instant::now() });
+        }
+    }
+
+    /// Check idle connections periodically, until the pool is dropped
+    fn spawn_reaper(inner: &Arc<PoolInner>) {
+        if inner.options.idle_timeout.is_none() && inner.options.min_connections == 0 {
+            return;
+        }
+        let period = inner.options.idle_timeout.map_or(REAP_INTERVAL, |t| t.min(REAP_INTERVAL))
+                .max(Duration::from_millis(10));
+        let pool: Weak<PoolInner> = Arc::downgrade(inner);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(period);
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                let inner = match pool.upgrade() {
+                    Some(v) => v,
+                    None => return
+                };
+                inner.prune();
+                inner.refill().await;
+            }
+        });
+    }
+}
+
+impl Deref for PoolConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PoolConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.connection.as_mut().unwrap()
+    }
+}
+
+impl Drop for PoolConnection {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            if let Ok(mut idle) = self.pool.idle.lock() {
+                idle.push(IdleConnection { connection, since: Instant::now() });
+            }
+            self.pool.prune();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use hyper::{Body, Response, Server};
+    use hyper::service::{make_service_fn, service_fn};
+
+    /// Options of a node answering every request, closing connections after a response unless `keep_alive`
+    fn node(keep_alive: bool) -> ConnectOptions {
+        let make = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::from("{}"))) }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).http1_keepalive(keep_alive).serve(make);
+        let port = server.local_addr().port();
+        tokio::spawn(server);
+        ConnectOptions::new("127.0.0.1", port)
+    }
+
+    #[tokio::test]
+    async fn idle_connections_are_closed_without_acquire() {
+        let pool = PoolOptions::new(&node(true))
+                .idle_timeout(Some(Duration::from_millis(50)))
+                .connect().await.unwrap();
+        let (a, b) = (pool.acquire().await.unwrap(), pool.acquire().await.unwrap());
+        drop((a, b));
+        assert_eq!(pool.idle(), 2);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(pool.idle(), 0);
+    }
+
+    #[tokio::test]
+    async fn expired_connections_are_pruned_on_release() {
+        let pool = PoolOptions::new(&node(true))
+                .idle_timeout(Some(Duration::from_secs(3600)))
+                .connect().await.unwrap();
+        let a = pool.acquire().await.unwrap();
+        drop(pool.acquire().await.unwrap());
+        pool.inner.idle.lock().unwrap()[0].since -= Duration::from_secs(7200);
+        drop(a);
+        assert_eq!(pool.idle(), 1);
+    }
+
+    #[tokio::test]
+    async fn min_connections_are_kept() {
+        let pool = PoolOptions::new(&node(true))
+                .min_connections(2)
+                .idle_timeout(Some(Duration::from_millis(50)))
+                .connect().await.unwrap();
+        drop((pool.acquire().await.unwrap(), pool.acquire().await.unwrap(), pool.acquire().await.unwrap()));
+        assert_eq!(pool.idle(), 3);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(pool.idle(), 2);
+    }
+
+    #[tokio::test]
+    async fn min_connections_are_refilled() {
+        let pool = PoolOptions::new(&node(false))
+                .min_connections(2)
+                .idle_timeout(Some(Duration::from_millis(50)))
+                .connect().await.unwrap();
+        // the node closes both connections after answering
+        let (a, b) = (pool.acquire().await.unwrap(), pool.acquire().await.unwrap());
+        a.ready().await.unwrap();
+        b.ready().await.unwrap();
+        drop((a, b));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // closed connections are dropped by acquire
+        drop(pool.acquire().await.unwrap());
+        assert_eq!(pool.idle(), 1);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(pool.idle(), 2);
+    }
+
+    #[tokio::test]
+    async fn reaper_ends_with_the_pool() {
+        let pool = PoolOptions::new(&node(true))
+                .idle_timeout(Some(Duration::from_millis(10)))
+                .connect().await.unwrap();
+        let inner = Arc::downgrade(&pool.inner);
+        drop(pool);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(inner.upgrade().is_none());
+    }
+}
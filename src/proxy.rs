@@ -0,0 +1,238 @@
+use std::error::Error;
+#[cfg(feature = "socks5")]
+use std::net::IpAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use crate::connect::{connect_host, ConnectOptions, Scheme};
+use crate::error::RqliteError;
+use crate::url::{config, parse_host, percent_decode};
+
+/// Port used when a proxy url has none
+const DEFAULT_PROXY_PORT: u16 = 80;
+
+/// Max size of the response to a CONNECT request
+const MAX_RESPONSE_SIZE: usize = 8192;
+
+/// Http proxy parsed from `http://[user[:pass]@]host[:port]`
+#[derive(Clone, Debug)]
+pub(crate) struct HttpProxy {
+    host: String,
+    port: u16,
+    /// Value of the Proxy-Authorization header
+    pub(crate) authorization: Option<String>
+}
+
+impl HttpProxy {
+    pub(crate) fn parse(url: &str) -> Result<HttpProxy, RqliteError> {
+        let rest = match url.split_once("://") {
+            Some(("http", rest)) => rest,
+            Some((scheme, _)) => return Err(config(format!("Unsupported proxy scheme {}", scheme))),
+            None => url
+        };
+        let rest = rest.trim_end_matches('/');
+        let (userinfo, host) = match rest.rsplit_once('@') {
+            Some((userinfo, host)) => (Some(userinfo), host),
+            None => (None, rest)
+        };
+        let (host, port) = parse_host(host, DEFAULT_PROXY_PORT)?;
+        let authorization = match userinfo {
+            Some(userinfo) => {
+                let (user, pass) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+                let credentials = format!("{}:{}", percent_decode(user)?, percent_decode(pass)?);
+                Some(format!("Basic {}", base64::encode(credentials)))
+            },
+            None => None
+        };
+        Ok(HttpProxy { host, port, authorization })
+    }
+
+    /// Open a tcp connection to the proxy
+    ///
+    /// Https and HTTP/2 connections are tunneled with a CONNECT request, other requests
+    /// are forwarded by the proxy, in which case the proxy is returned along the socket
+    pub(crate) async fn connect(&self, options: &ConnectOptions) -> Result<(TcpStream, Option<HttpProxy>), Box<dyn Error + Send + Sync>> {
+        let mut sock = connect_host(&self.host, self.port, options.local_address).await?;
+        if matches!(options.scheme, Scheme::HTTP) && !options.http2 {
+            return Ok((sock, Some(self.clone())));
+        }
+        self.tunnel(&mut sock, &authority(&options.host, options.port)).await?;
+        Ok((sock, None))
+    }
+
+    /// Ask the proxy to open a tunnel to `target`
+    async fn tunnel(&self, sock: &mut TcpStream, target: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", target);
+        if let Some(authorization) = &self.authorization {
+            request.push_str(&format!("Proxy-Authorization: {}\r\n", authorization));
+        }
+        request.push_str("\r\n");
+        sock.write_all(request.as_bytes()).await?;
+
+        // read byte by byte, data following the headers belongs to the tunnel
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_RESPONSE_SIZE {
+                return Err("Proxy response too large".into());
+            }
+            match sock.read_u8().await {
+                Ok(byte) => response.push(byte),
+                Err(_) => return Err("Proxy closed the connection".into())
+            }
+        }
+        let response = String::from_utf8_lossy(&response);
+        let status = response.lines().next().unwrap_or_default();
+        match status.split_whitespace().nth(1) {
+            Some("200") => Ok(()),
+            _ => Err(format!("Proxy refused tunnel to {}: {}", target, status).into())
+        }
+    }
+}
+
+/// `host:port`, with ipv6 hosts enclosed in brackets
+pub(crate) fn authority(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// Host without the brackets enclosing an ipv6 address, `[::1]` is `::1`
+pub(crate) fn unbracket(host: &str) -> &str {
+    host.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(host)
+}
+
+/// Port used when a socks5 proxy url has none
+#[cfg(feature = "socks5")]
+const DEFAULT_SOCKS5_PORT: u16 = 1080;
+
+/// Socks5 proxy parsed from `socks5://[user[:pass]@]host[:port]`
+#[cfg(feature = "socks5")]
+#[derive(Clone, Debug)]
+pub(crate) struct Socks5Proxy {
+    host: String,
+    port: u16,
+    credentials: Option<(String, String)>
+}
+
+#[cfg(feature = "socks5")]
+impl Socks5Proxy {
+    pub(crate) fn parse(url: &str) -> Result<Socks5Proxy, RqliteError> {
+        let rest = match url.split_once("://") {
+            Some(("socks5" | "socks5h", rest)) => rest,
+            Some((scheme, _)) => return Err(config(format!("Unsupported proxy scheme {}", scheme))),
+            None => url
+        };
+        let rest = rest.trim_end_matches('/');
+        let (userinfo, host) = match rest.rsplit_once('@') {
+            Some((userinfo, host)) => (Some(userinfo), host),
+            None => (None, rest)
+        };
+        let (host, port) = parse_host(host, DEFAULT_SOCKS5_PORT)?;
+        let credentials = match userinfo {
+            Some(userinfo) => {
+                let (user, pass) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+                let (user, pass) = (percent_decode(user)?, percent_decode(pass)?);
+                if user.len() > 255 || pass.len() > 255 {
+                    return Err(config("Socks5 credentials longer than 255 bytes".to_owned()));
+                }
+                Some((user, pass))
+            },
+            None => None
+        };
+        Ok(Socks5Proxy { host, port, credentials })
+    }
+
+    /// Open a tcp connection to `host:port` through the proxy
+    ///
+    /// Host names are resolved by the proxy
+    pub(crate) async fn connect(&self, host: &str, port: u16, local: Option<IpAddr>) -> Result<TcpStream, Box<dyn Error + Send + Sync>> {
+        let mut sock = connect_host(&self.host, self.port, local).await?;
+
+        // greeting, offering username/password authentication only if configured
+        match self.credentials {
+            Some(_) => sock.write_all(&[5, 2, 0, 2]).await?,
+            None => sock.write_all(&[5, 1, 0]).await?
+        }
+        let mut reply = [0u8; 2];
+        sock.read_exact(&mut reply).await?;
+        match (reply, &self.credentials) {
+            ([5, 0], _) => {},
+            ([5, 2], Some((user, pass))) => {
+                let mut auth = vec![1, user.len() as u8];
+                auth.extend_from_slice(user.as_bytes());
+                auth.push(pass.len() as u8);
+                auth.extend_from_slice(pass.as_bytes());
+                sock.write_all(&auth).await?;
+                sock.read_exact(&mut reply).await?;
+                if reply[1] != 0 {
+                    return Err("Socks5 proxy rejected credentials".into());
+                }
+            },
+            _ => return Err("Socks5 proxy has no acceptable authentication method".into())
+        }
+
+        let mut request = vec![5, 1, 0];
+        match host.parse::<std::net::IpAddr>() {
+            Ok(std::net::IpAddr::V4(ip)) => {
+                request.push(1);
+                request.extend_from_slice(&ip.octets());
+            },
+            Ok(std::net::IpAddr::V6(ip)) => {
+                request.push(4);
+                request.extend_from_slice(&ip.octets());
+            },
+            Err(_) if host.len() <= 255 => {
+                request.push(3);
+                request.push(host.len() as u8);
+                request.extend_from_slice(host.as_bytes());
+            },
+            Err(_) => return Err("Host name too long for socks5".into())
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        sock.write_all(&request).await?;
+
+        let mut reply = [0u8; 4];
+        sock.read_exact(&mut reply).await?;
+        if reply[1] != 0 {
+            return Err(format!("Socks5 proxy failed to connect to {} (code {})", authority(host, port), reply[1]).into());
+        }
+        // skip bound address and port
+        let len = match reply[3] {
+            1 => 4,
+            4 => 16,
+            3 => sock.read_u8().await? as usize,
+            _ => return Err("Invalid socks5 proxy reply".into())
+        };
+        let mut bound = vec![0u8; len + 2];
+        sock.read_exact(&mut bound).await?;
+        Ok(sock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authority_brackets_ipv6() {
+        assert_eq!(authority("::1", 4001), "[::1]:4001");
+        assert_eq!(authority("fe80::1", 80), "[fe80::1]:80");
+    }
+
+    #[test]
+    fn authority_keeps_ipv4_and_names() {
+        assert_eq!(authority("127.0.0.1", 4001), "127.0.0.1:4001");
+        assert_eq!(authority("db1.local", 4001), "db1.local:4001");
+    }
+
+    #[test]
+    fn unbracket_ipv6() {
+        assert_eq!(unbracket("[::1]"), "::1");
+        assert_eq!(unbracket("::1"), "::1");
+        assert_eq!(unbracket("127.0.0.1"), "127.0.0.1");
+        assert_eq!(unbracket("db1.local"), "db1.local");
+        // only a pair of brackets is removed
+        assert_eq!(unbracket("[::1"), "[::1");
+    }
+}
@@ -0,0 +1,231 @@
+use std::marker::PhantomData;
+use serde::de::DeserializeOwned;
+use crate::connect::Connection;
+use crate::cursor::Cursor;
+use crate::error::RqliteError;
+use crate::row::{FromRow, Row};
+use crate::types::IntoParams;
+
+/// Builder of parameterized SELECT queries
+///
+/// Table, columns and ordering are written as is in the query, values must be passed
+/// as parameters of [`Query::filter()`].
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// use rqlite::Query;
+///
+/// let rows = Query::select("foo")
+///     .columns(&["id", "name"])
+///     .filter("id > ?", par!(1))
+///     .filter("name LIKE ?", par!("f%"))
+///     .order_by("id DESC")
+///     .limit(10)
+///     .fetch_all(&conn).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    table: String,
+    columns: Vec<String>,
+    filters: Vec<String>,
+    params: Vec<serde_json::Value>,
+    /// First error converting parameters, returned when the query runs
+    error: Option<String>,
+    order_by: Vec<String>,
+    limit: Option<u64>,
+    offset: Option<u64>
+}
+
+impl Query {
+    /// Select rows of `table`, all columns are selected unless [`Query::columns()`] is called
+    pub fn select(table: &str) -> Query {
+        Query {
+            table: table.to_owned(),
+            columns: Vec::new(),
+            filters: Vec::new(),
+            params: Vec::new(),
+            error: None,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None
+        }
+    }
+
+    /// Add selected columns or expressions
+    pub fn columns(&mut self, columns: &[&str]) -> &mut Query {
+        self.columns.extend(columns.iter().map(|c| (*c).to_owned()));
+        self
+    }
+
+    /// Add a WHERE condition with its parameters, conditions are joined with AND
+    ///
+    /// Parameters that cannot be converted make the query fail with `RqliteError::DataSer` when it runs.
+    pub fn filter(&mut self, condition: &str, params: impl IntoParams) -> &mut Query {
+        self.filters.push(condition.to_owned());
+        match params.into_params() {
+            Ok(params) => self.params.extend(params),
+            Err(e) => {
+                self.error.get_or_insert(e.to_string());
+            }
+        }
+        self
+    }
+
+    /// Add an ORDER BY term, like `name` or `id DESC`
+    pub fn order_by(&mut self, term: &str) -> &mut Query {
+        self.order_by.push(term.to_owned());
+        self
+    }
+
+    /// Maximum number of rows
+    pub fn limit(&mut self, limit: u64) -> &mut Query {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Number of rows to skip
+    pub fn offset(&mut self, offset: u64) -> &mut Query {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Sql of the query
+    pub fn sql(&self) -> String {
+        let columns = if self.columns.is_empty() { "*".to_owned() } else { self.columns.join(", ") };
+        let mut sql = format!("SELECT {} FROM {}", columns, self.table);
+        if !self.filters.is_empty() {
+            let filters: Vec<String> = self.filters.iter().map(|f| format!("({})", f)).collect();
+            sql.push_str(" WHERE ");
+            sql.push_str(&filters.join(" AND "));
+        }
+        if !self.order_by.is_empty() {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&self.order_by.join(", "));
+        }
+        match (self.limit, self.offset) {
+            (Some(limit), Some(offset)) => sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset)),
+            (Some(limit), None) => sql.push_str(&format!(" LIMIT {}", limit)),
+            // sqlite needs a LIMIT before OFFSET, negative means no limit
+            (None, Some(offset)) => sql.push_str(&format!(" LIMIT -1 OFFSET {}", offset)),
+            (None, None) => ()
+        }
+        sql
+    }
+
+    /// Parameters of the query, in order
+    pub fn params(&self) -> &[serde_json::Value] {
+        &self.params
+    }
+
+    /// Parameters to send, or the error of [`Query::filter()`]
+    fn checked_params(&self) -> Result<Vec<serde_json::Value>, RqliteError> {
+        match &self.error {
+            Some(message) => Err(RqliteError::DataSer(message.clone())),
+            None => Ok(self.params.clone())
+        }
+    }
+
+    /// Run the query on `conn`
+    ///
+    /// See [`Connection::query()`]
+    pub async fn query<'a>(&self, conn: &'a Connection) -> Result<Cursor<'a>, RqliteError> {
+        conn.query(&self.sql(), self.checked_params()?).await
+    }
+
+    /// Run the query on `conn` and get all rows
+    ///
+    /// See [`Connection::fetch_all()`]
+    pub async fn fetch_all(&self, conn: &Connection) -> Result<Vec<Row>, RqliteError> {
+        conn.fetch_all(&self.sql(), self.checked_params()?).await
+    }
+
+    /// Run the query on `conn`, it must return exactly one row
+    ///
+    /// See [`Connection::fetch_one()`]
+    pub async fn fetch_one(&self, conn: &Connection) -> Result<Row, RqliteError> {
+        conn.fetch_one(&self.sql(), self.checked_params()?).await
+    }
+
+    /// Run the query on `conn`, it must return at most one row
+    ///
+    /// See [`Connection::fetch_optional()`]
+    pub async fn fetch_optional(&self, conn: &Connection) -> Result<Option<Row>, RqliteError> {
+        conn.fetch_optional(&self.sql(), self.checked_params()?).await
+    }
+
+    /// Run the query on `conn` and deserialize all rows into `T`
+    ///
+    /// See [`Connection::query_as()`]
+    pub async fn fetch_as<T: DeserializeOwned>(&self, conn: &Connection) -> Result<Vec<T>, RqliteError> {
+        conn.query_as(&self.sql(), self.checked_params()?).await
+    }
+}
+
+/// Query built by the `query!` macro, rows are read into `T`
+///
+/// See [`query!`](crate::query!)
+#[derive(Clone, Debug)]
+pub struct TypedQuery<T> {
+    sql: &'static str,
+    params: Vec<serde_json::Value>,
+    row: PhantomData<fn() -> T>
+}
+
+impl<T: FromRow> TypedQuery<T> {
+    #[doc(hidden)]
+    pub fn new(sql: &'static str, params: Vec<serde_json::Value>) -> TypedQuery<T> {
+        TypedQuery { sql, params, row: PhantomData }
+    }
+
+    /// Sql of the query
+    pub fn sql(&self) -> &'static str {
+        self.sql
+    }
+
+    /// Parameters of the query, in order
+    pub fn params(&self) -> &[serde_json::Value] {
+        &self.params
+    }
+
+    /// Run the query on `conn` and read all rows
+    ///
+    /// Returns RqliteError if the query fails, `RqliteError::DataSer` if a row cannot be read
+    pub async fn fetch_all(&self, conn: &Connection) -> Result<Vec<T>, RqliteError> {
+        conn.fetch_all(self.sql, self.params.clone()).await?
+            .iter()
+            .map(read_row)
+            .collect()
+    }
+
+    /// Run the query on `conn`, it must return exactly one row
+    ///
+    /// Returns RqliteError if the query fails or doesn't return exactly one row
+    pub async fn fetch_one(&self, conn: &Connection) -> Result<T, RqliteError> {
+        read_row(&conn.fetch_one(self.sql, self.params.clone()).await?)
+    }
+
+    /// Run the query on `conn`, it must return at most one row
+    ///
+    /// Returns RqliteError if the query fails or returns more than one row
+    pub async fn fetch_optional(&self, conn: &Connection) -> Result<Option<T>, RqliteError> {
+        conn.fetch_optional(self.sql, self.params.clone()).await?
+            .as_ref()
+            .map(read_row)
+            .transpose()
+    }
+
+    /// Run the statement on `conn` as a write
+    ///
+    /// See [`Connection::execute()`]
+    pub async fn execute<'a>(&self, conn: &'a Connection) -> Result<Cursor<'a>, RqliteError> {
+        conn.execute(self.sql, self.params.clone()).await
+    }
+}
+
+fn read_row<T: FromRow>(row: &Row) -> Result<T, RqliteError> {
+    T::from_row(row).map_err(|e| RqliteError::DataSer(e.to_string()))
+}
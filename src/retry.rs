@@ -0,0 +1,151 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::error::RqliteError;
+
+/// Class of errors a [`RetryPolicy`] can retry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorClass {
+    /// Networking error or connection closed by the node
+    Connection,
+    /// Request timeout elapsed
+    Timeout,
+    /// Node is not the leader or leadership changed while applying statements
+    Leadership
+}
+
+impl ErrorClass {
+    /// Class of `error`, None if it is not transient
+    pub(crate) fn of(error: &RqliteError) -> Option<ErrorClass> {
+        match error {
+            RqliteError::Connection(_) | RqliteError::Http(_) | RqliteError::Dns { .. } | RqliteError::Tcp { .. } | RqliteError::Handshake(_) => Some(ErrorClass::Connection),
+            e if e.is_timeout()    => Some(ErrorClass::Timeout),
+            e if e.is_not_leader() => Some(ErrorClass::Leadership),
+            _ => None
+        }
+    }
+}
+
+/// Check if an error message reported by rqlite is caused by a leadership change
+pub(crate) fn is_leadership_error(message: &str) -> bool {
+    message.contains("not leader") || message.contains("leadership lost") || message.contains("leader not found")
+}
+
+/// Retry requests failing with transient errors, with exponential backoff
+///
+/// Delay before retry n is `base_delay * 2^n`, capped at `max_delay`.
+/// With jitter enabled a random delay of up to half of it is removed.
+/// ```no_run
+/// # use rqlite::*;
+/// # use std::time::Duration;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// use rqlite::{ConnectOptions, ErrorClass, RetryPolicy};
+///
+/// let conn = ConnectOptions::new("my.node.local", 4001)
+///        .retry_policy(RetryPolicy::new()
+///            .max_attempts(5)
+///            .base_delay(Duration::from_millis(50))
+///            .retry_on(&[ErrorClass::Connection, ErrorClass::Leadership]))
+///        .connect().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    #[serde(with = "crate::config::duration")]
+    base_delay: Duration,
+    #[serde(with = "crate::config::duration")]
+    max_delay: Duration,
+    jitter: bool,
+    retry_on: Vec<ErrorClass>
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            retry_on: vec![ErrorClass::Connection, ErrorClass::Leadership]
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create retry policy, 3 attempts from 100ms to 5s with jitter,
+    /// retrying connection and leadership errors
+    pub fn new() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /// Policy sending every request only once
+    pub fn none() -> RetryPolicy {
+        RetryPolicy { max_attempts: 1, ..RetryPolicy::default() }
+    }
+
+    /// Set max number of attempts, first one included
+    pub fn max_attempts(&mut self, attempts: usize) -> &mut RetryPolicy {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Set delay before the first retry
+    pub fn base_delay(&mut self, delay: Duration) -> &mut RetryPolicy {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Set max delay between two attempts
+    pub fn max_delay(&mut self, delay: Duration) -> &mut RetryPolicy {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Randomize delays, so clients don't retry all at the same time
+    pub fn jitter(&mut self, jitter: bool) -> &mut RetryPolicy {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set classes of errors to retry
+    pub fn retry_on(&mut self, classes: &[ErrorClass]) -> &mut RetryPolicy {
+        self.retry_on = classes.to_vec();
+        self
+    }
+
+    /// Check if the request should be sent again after `attempt` failed with `error`
+    ///
+    /// `attempt` starts at 0
+    pub(crate) fn should_retry(&self, attempt: usize, error: &RqliteError) -> bool {
+        self.can_retry(attempt) && ErrorClass::of(error).is_some_and(|c| self.retries(c))
+    }
+
+    /// Check if there are attempts left after `attempt`
+    pub(crate) fn can_retry(&self, attempt: usize) -> bool {
+        attempt + 1 < self.max_attempts
+    }
+
+    /// Check if errors of `class` are retried
+    pub(crate) fn retries(&self, class: ErrorClass) -> bool {
+        self.retry_on.contains(&class)
+    }
+
+    /// Delay to wait after `attempt` failed
+    pub(crate) fn delay(&self, attempt: usize) -> Duration {
+        let delay = self.base_delay
+                .checked_mul(1 << attempt.min(16) as u32)
+                .map_or(self.max_delay, |d| d.min(self.max_delay));
+        if !self.jitter {
+            return delay;
+        }
+        let random = RandomState::new().build_hasher().finish();
+        let half = delay.as_millis() as u64 / 2;
+        delay - Duration::from_millis(random % (half + 1))
+    }
+}
@@ -1,27 +1,344 @@
+use serde::Deserialize;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::error::Error;
 use std::io::ErrorKind;
+use std::sync::Arc;
+use crate::convert;
+use crate::types::{Json, Type};
 
 #[derive(Debug)]
 pub struct Row {
+    columns: Arc<Vec<String>>,
+    types: Arc<Vec<Type>>,
     row: Vec<Value>
 }
 
 /// SQL row
 impl Row {
-    pub(crate) fn new(row: Vec<Value>) -> Row {
-        Row { row }
+    pub(crate) fn new(columns: Arc<Vec<String>>, types: Arc<Vec<Type>>, row: Vec<Value>) -> Row {
+        Row { columns, types, row }
     }
 
     /// Get n element in row
+    ///
+    /// Blob columns, sent by rqlite in base64, are decoded so they can be read as `Vec<u8>`.
+    /// NULL is read as `None` into an `Option<T>`.
     /// Return error if element cannot be formatted
-    pub fn get<T: DeserializeOwned>(&self, id: usize) -> Result<T, Box<dyn Error>> {
+    pub fn get<T: DeserializeOwned>(&self, id: usize) -> Result<T, Box<dyn Error + Send + Sync>> {
         if id >= self.row.len() {
             return Err(Box::new(std::io::Error::new(ErrorKind::NotFound, format!("Row element with id {} doesn't exist", id))));
         }
 
-        let val: T = serde_json::from_value(self.row[id].clone())?;
-        Ok(val)
+        if self.row[id].is_null() {
+            return serde_json::from_value(Value::Null).map_err(|_| {
+                let message = format!("Row element with id {} is NULL, read it as an Option", id);
+                Box::new(std::io::Error::new(ErrorKind::InvalidData, message)) as Box<dyn Error + Send + Sync>
+            });
+        }
+
+        let mut blob = None;
+        if let (Some(Type::Blob), Value::String(encoded)) = (self.types.get(id), &self.row[id]) {
+            if let Ok(bytes) = base64::decode(encoded) {
+                // still readable as the base64 string otherwise
+                if let Ok(val) = serde_json::from_value(bytes_value(&bytes)) {
+                    return Ok(val);
+                }
+                blob = Some(bytes);
+            }
+        }
+        match T::deserialize(&self.row[id]) {
+            Ok(val) => Ok(val),
+            Err(e) => {
+                // rqlite reports no type for expressions whose first value is NULL
+                if let (None | Some(Type::Null), Value::String(encoded)) = (self.types.get(id), &self.row[id]) {
+                    if let Ok(bytes) = base64::decode(encoded) {
+                        if let Ok(val) = serde_json::from_value(bytes_value(&bytes)) {
+                            return Ok(val);
+                        }
+                    }
+                }
+                // like dates stored by sqlite or uuids stored as blobs
+                for value in convert::alternatives(&self.row[id], blob.as_deref()) {
+                    if let Ok(val) = serde_json::from_value(value) {
+                        return Ok(val);
+                    }
+                }
+                Err(Box::new(e))
+            }
+        }
+    }
+
+    /// Borrow n element in row, like a `&str` of a text column
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let mut cur = conn.query("SELECT name FROM foo", par!()).await?;
+    /// while let Some(row) = cur.next_row() {
+    ///     let name: &str = row.get_ref(0)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The element is read as sent by rqlite: blobs are base64 text and dates are not converted,
+    /// see [`Row::get()`] for these.
+    /// Return error if element cannot be formatted
+    pub fn get_ref<'a, T: Deserialize<'a>>(&'a self, id: usize) -> Result<T, Box<dyn Error + Send + Sync>> {
+        match self.row.get(id) {
+            Some(value) => Ok(T::deserialize(value)?),
+            None => Err(Box::new(std::io::Error::new(ErrorKind::NotFound, format!("Row element with id {} doesn't exist", id))))
+        }
+    }
+
+    /// Move n element out of the row, leaving NULL in its place
+    ///
+    /// Large text is read without being copied, elements are converted like [`Row::get()`].
+    /// Text is taken out of the row even if it cannot be read.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let mut row = conn.fetch_one("SELECT body FROM posts WHERE id = ?", par!(1)).await?;
+    /// let body: String = row.take(0)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take<T: DeserializeOwned>(&mut self, id: usize) -> Result<T, Box<dyn Error + Send + Sync>> {
+        let text = matches!(self.types.get(id), Some(Type::Text)) && self.row.get(id).is_some_and(Value::is_string);
+        if !text {
+            let val = self.get(id)?;
+            self.row[id] = Value::Null;
+            return Ok(val);
+        }
+        // conversions need the text, which is moved
+        let alternatives = convert::alternatives(&self.row[id], None);
+        let value = std::mem::take(&mut self.row[id]);
+        let error = match serde_json::from_value::<T>(value) {
+            Ok(val) => return Ok(val),
+            Err(e) => e
+        };
+        for value in alternatives {
+            if let Ok(val) = serde_json::from_value(value) {
+                return Ok(val);
+            }
+        }
+        Err(Box::new(error))
+    }
+
+    /// Get element of column `name` in row
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let mut cur = conn.query("SELECT id, email FROM foo", par!()).await?;
+    /// while let Some(row) = cur.next_row() {
+    ///     let email: String = row.get_by_name("email")?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Return error if there is no such column or element cannot be formatted
+    pub fn get_by_name<T: DeserializeOwned>(&self, name: &str) -> Result<T, Box<dyn Error + Send + Sync>> {
+        match self.columns.iter().position(|c| c == name) {
+            Some(id) => self.get(id),
+            None => Err(Box::new(std::io::Error::new(ErrorKind::NotFound, format!("Row has no column named {}", name))))
+        }
+    }
+
+    /// Get n element in row, parsed from json text
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let mut cur = conn.query("SELECT data FROM foo", par!()).await?;
+    /// while let Some(row) = cur.next_row() {
+    ///     let data: serde_json::Value = row.get_json(0)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Return error if element is not valid json for `T`, see [`Json`]
+    pub fn get_json<T: DeserializeOwned>(&self, id: usize) -> Result<T, Box<dyn Error + Send + Sync>> {
+        self.get::<Json<T>>(id).map(|json| json.0)
+    }
+
+    /// Get element of column `name` in row, parsed from json text
+    ///
+    /// Return error if there is no such column or element is not valid json for `T`
+    pub fn get_json_by_name<T: DeserializeOwned>(&self, name: &str) -> Result<T, Box<dyn Error + Send + Sync>> {
+        self.get_by_name::<Json<T>>(name).map(|json| json.0)
+    }
+
+    /// Map of column names to elements, as rqlite sends them
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let row = conn.fetch_one("SELECT id, name FROM foo WHERE id = ?", par!(1)).await?;
+    /// let map = row.as_map();
+    /// println!("{}", map["name"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn as_map(&self) -> serde_json::Map<String, Value> {
+        self.columns.iter().cloned().zip(self.row.iter().cloned()).collect()
+    }
+
+    /// Consume the row into a map of column names to elements
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # use std::collections::HashMap;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// let rows = conn.fetch_all("SELECT id, name FROM foo", par!()).await?;
+    /// let maps: Vec<HashMap<String, Value>> = rows.into_iter().map(Row::into_map).collect();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_map(self) -> HashMap<String, Value> {
+        self.columns.iter().cloned().zip(self.row).collect()
+    }
+
+    /// Deserialize the row into `T`, fields are matched by column name
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # use serde::Deserialize;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+    /// #[derive(Deserialize)]
+    /// struct Foo {
+    ///     id: i64,
+    ///     name: String
+    /// }
+    ///
+    /// let foo: Foo = conn.fetch_one("SELECT id, name FROM foo WHERE id = ?", par!(1)).await?.deserialize()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Return error if the row cannot be deserialized into `T`
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, Box<dyn Error + Send + Sync>> {
+        Ok(serde_json::from_value(Value::Object(self.as_map()))?)
+    }
+
+    /// Element `id` as sent by rqlite
+    pub(crate) fn value(&self, id: usize) -> Option<&Value> {
+        self.row.get(id)
+    }
+
+    /// Column names of the row, in the same order as elements
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Column types of the row, in the same order as elements
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+}
+
+/// Decoded blob as a json array of bytes
+fn bytes_value(bytes: &[u8]) -> Value {
+    Value::Array(bytes.iter().map(|b| Value::from(*b)).collect())
+}
+
+/// Construct a type from a [`Row`]
+///
+/// With the `derive` feature it can be derived for structs,
+/// named fields are matched by column name and tuple fields by position.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # #[cfg(feature = "derive")]
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// use rqlite::FromRow;
+///
+/// #[derive(FromRow)]
+/// struct Foo {
+///     id: i64,
+///     #[rqlite(rename = "name")]
+///     foo_name: String,
+///     #[rqlite(index = 2)]
+///     age: Option<u8>
+/// }
+///
+/// let mut cur = conn.query("SELECT id, name, age FROM foo", par!()).await?;
+/// while let Some(row) = cur.next_row() {
+///     let foo = Foo::from_row(&row)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub trait FromRow: Sized {
+    /// Construct `Self` from `row`
+    fn from_row(row: &Row) -> Result<Self, Box<dyn Error + Send + Sync>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ColumnTypes;
+
+    /// Row of a `SELECT` with one column of each declared type, as rqlite sends it
+    fn row(values: Value) -> Row {
+        let columns = vec!["i".to_owned(), "r".to_owned(), "t".to_owned(), "b".to_owned(), "n".to_owned()];
+        let types = match serde_json::from_str(r#"["integer", "real", "text", "blob", "numeric"]"#).unwrap() {
+            ColumnTypes::List(types) => types,
+            ColumnTypes::Named(_) => unreachable!()
+        };
+        let values = match values {
+            Value::Array(values) => values,
+            _ => unreachable!()
+        };
+        Row::new(Arc::new(columns), Arc::new(types), values)
+    }
+
+    #[test]
+    fn null_is_none_for_every_type() {
+        let row = row(serde_json::json!([null, null, null, null, null]));
+        assert!(matches!(row.types.as_slice(), [Type::Integer, Type::Real, Type::Text, Type::Blob, Type::Numeric]));
+        for id in 0..5 {
+            assert_eq!(row.get::<Option<i64>>(id).unwrap(), None, "column {}", id);
+            assert_eq!(row.get::<Option<f64>>(id).unwrap(), None, "column {}", id);
+            assert_eq!(row.get::<Option<String>>(id).unwrap(), None, "column {}", id);
+            assert_eq!(row.get::<Option<Vec<u8>>>(id).unwrap(), None, "column {}", id);
+        }
+    }
+
+    #[test]
+    fn null_is_an_error_without_option() {
+        let row = row(serde_json::json!([null, null, null, null, null]));
+        for id in 0..5 {
+            assert!(row.get::<i64>(id).is_err(), "column {}", id);
+            assert!(row.get::<f64>(id).is_err(), "column {}", id);
+            assert!(row.get::<String>(id).is_err(), "column {}", id);
+            assert!(row.get::<Vec<u8>>(id).is_err(), "column {}", id);
+        }
+        let message = row.get::<i64>(0).unwrap_err().to_string();
+        assert!(message.contains("is NULL, read it as an Option"), "{}", message);
+    }
+
+    #[test]
+    fn values_are_some_for_every_type() {
+        // blobs are sent in base64
+        let row = row(serde_json::json!([42, 1.5, "fiona", "AAEC", 7]));
+        assert_eq!(row.get::<Option<i64>>(0).unwrap(), Some(42));
+        assert_eq!(row.get::<Option<f64>>(1).unwrap(), Some(1.5));
+        assert_eq!(row.get::<Option<String>>(2).unwrap(), Some("fiona".to_owned()));
+        assert_eq!(row.get::<Option<Vec<u8>>>(3).unwrap(), Some(vec![0, 1, 2]));
+        assert_eq!(row.get::<Option<i64>>(4).unwrap(), Some(7));
     }
 }
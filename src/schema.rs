@@ -0,0 +1,138 @@
+use serde::de::DeserializeOwned;
+use crate::connect::Connection;
+use crate::error::RqliteError;
+use crate::row::Row;
+
+/// Table of the database
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    pub name: String,
+    /// `CREATE TABLE` statement of the table
+    pub sql: Option<String>
+}
+
+/// Column of a table, as reported by `PRAGMA table_info`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    /// Position of the column in the table
+    pub cid: i64,
+    pub name: String,
+    /// Declared type, empty if the column has none
+    pub type_name: String,
+    pub not_null: bool,
+    /// Default value expression
+    pub default: Option<String>,
+    /// Position of the column in the primary key, 0 if it is not part of it
+    pub primary_key: i64
+}
+
+/// Index of a table, as reported by `PRAGMA index_list`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Index {
+    pub name: String,
+    pub unique: bool,
+    /// `c` for `CREATE INDEX`, `u` for a UNIQUE constraint, `pk` for a PRIMARY KEY constraint
+    pub origin: String,
+    /// Index has a WHERE clause
+    pub partial: bool,
+    /// Indexed columns in order, expressions are not listed
+    pub columns: Vec<String>
+}
+
+impl Connection {
+    /// Tables of the database, sorted by name
+    ///
+    /// Internal `sqlite_` tables are not listed.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    ///        .connect().await?;
+    /// for table in conn.tables().await? {
+    ///     println!("{}", table.name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError if the query fails
+    pub async fn tables(&self) -> Result<Vec<Table>, RqliteError> {
+        let rows = self.fetch_all("SELECT name, sql FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' ORDER BY name", Vec::new()).await?;
+        rows.iter()
+            .map(|row| Ok(Table { name: get(row, 0)?, sql: get(row, 1)? }))
+            .collect()
+    }
+
+    /// Columns of `table`, in order
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    ///        .connect().await?;
+    /// for column in conn.columns("foo").await? {
+    ///     println!("{} {}", column.name, column.type_name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError if the query fails, an unknown table has no columns
+    pub async fn columns(&self, table: &str) -> Result<Vec<Column>, RqliteError> {
+        let sql = "SELECT cid, name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?) ORDER BY cid";
+        let rows = self.fetch_all(sql, vec![table.into()]).await?;
+        rows.iter()
+            .map(|row| Ok(Column {
+                cid: get(row, 0)?,
+                name: get(row, 1)?,
+                type_name: get::<Option<String>>(row, 2)?.unwrap_or_default(),
+                not_null: get::<i64>(row, 3)? != 0,
+                default: get(row, 4)?,
+                primary_key: get(row, 5)?
+            }))
+            .collect()
+    }
+
+    /// Indexes of `table`, including the ones created for UNIQUE and PRIMARY KEY constraints
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    ///        .connect().await?;
+    /// for index in conn.indexes("foo").await? {
+    ///     println!("{} on {:?}", index.name, index.columns);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns RqliteError if the query fails, an unknown table has no indexes
+    pub async fn indexes(&self, table: &str) -> Result<Vec<Index>, RqliteError> {
+        let sql = "SELECT il.name, il.\"unique\", il.origin, il.partial, ii.name \
+                   FROM pragma_index_list(?) AS il LEFT JOIN pragma_index_info(il.name) AS ii \
+                   ORDER BY il.seq, ii.seqno";
+        let rows = self.fetch_all(sql, vec![table.into()]).await?;
+        let mut indexes: Vec<Index> = Vec::new();
+        for row in rows.iter() {
+            let name: String = get(row, 0)?;
+            let column: Option<String> = get(row, 4)?;
+            match indexes.last_mut() {
+                Some(index) if index.name == name => index.columns.extend(column),
+                _ => indexes.push(Index {
+                    name,
+                    unique: get::<i64>(row, 1)? != 0,
+                    origin: get(row, 2)?,
+                    partial: get::<i64>(row, 3)? != 0,
+                    columns: column.into_iter().collect()
+                })
+            }
+        }
+        Ok(indexes)
+    }
+}
+
+fn get<T: DeserializeOwned>(row: &Row, id: usize) -> Result<T, RqliteError> {
+    row.get(id).map_err(|e| RqliteError::DataSer(e.to_string()))
+}
@@ -0,0 +1,163 @@
+/// Piece of a sql text
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Token<'a> {
+    /// String or quoted identifier, with its quotes, `''` and `""` are escaped quotes
+    Quoted(&'a str),
+    /// `-- ...` or `/* ... */` comment, without the newline ending a line comment
+    Comment(&'a str),
+    /// `?` or `?NNN` placeholder
+    Param(&'a str),
+    /// `:name`, `@name` or `$name` parameter
+    Named(&'a str),
+    /// Any other text
+    Text(&'a str)
+}
+
+/// Split `sql` into tokens, so quotes, comments and placeholders are read the same way everywhere
+pub(crate) fn tokens(sql: &str) -> Tokens<'_> {
+    Tokens { sql, pos: 0 }
+}
+
+pub(crate) struct Tokens<'a> {
+    sql: &'a str,
+    pos: usize
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let bytes = self.sql.as_bytes();
+        let start = self.pos;
+        if start >= bytes.len() {
+            return None;
+        }
+        // delimiters are ascii, so every index below is a char boundary
+        let mut end = start + 1;
+        let token = match bytes[start] {
+            c @ (b'\'' | b'"' | b'`' | b'[') => {
+                let close = if c == b'[' { b']' } else { c };
+                loop {
+                    match bytes.get(end) {
+                        Some(&b) if b == close && close != b']' && bytes.get(end + 1) == Some(&close) => end += 2,
+                        Some(&b) if b == close => {
+                            end += 1;
+                            break;
+                        },
+                        Some(_) => end += 1,
+                        None => break
+                    }
+                }
+                Token::Quoted(&self.sql[start..end])
+            },
+            b'-' if bytes.get(end) == Some(&b'-') => {
+                while end < bytes.len() && bytes[end] != b'\n' {
+                    end += 1;
+                }
+                Token::Comment(&self.sql[start..end])
+            },
+            b'/' if bytes.get(end) == Some(&b'*') => {
+                end += 1;
+                while end < bytes.len() && !(bytes[end - 1] == b'*' && bytes[end] == b'/' && end > start + 2) {
+                    end += 1;
+                }
+                end = (end + 1).min(bytes.len());
+                Token::Comment(&self.sql[start..end])
+            },
+            b'?' => {
+                while end < bytes.len() && bytes[end].is_ascii_digit() {
+                    end += 1;
+                }
+                Token::Param(&self.sql[start..end])
+            },
+            b':' | b'@' | b'$' if is_named(self.sql, start) => {
+                while end < bytes.len() && is_word(bytes[end]) {
+                    end += 1;
+                }
+                Token::Named(&self.sql[start..end])
+            },
+            _ => {
+                while end < bytes.len() && !starts_token(self.sql, end) {
+                    end += 1;
+                }
+                Token::Text(&self.sql[start..end])
+            }
+        };
+        self.pos = end;
+        Some(token)
+    }
+}
+
+fn is_word(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+/// Check if the `:`, `@` or `$` at `pos` starts a named parameter, `$` is part of `a$b`
+fn is_named(sql: &str, pos: usize) -> bool {
+    let bytes = sql.as_bytes();
+    let next = sql[pos + 1..].chars().next();
+    if !next.is_some_and(|c| c.is_alphabetic() || c == '_') {
+        return false;
+    }
+    !(bytes[pos] == b'$' && pos > 0 && is_word(bytes[pos - 1]))
+}
+
+/// Check if a token other than text starts at `pos`
+fn starts_token(sql: &str, pos: usize) -> bool {
+    let bytes = sql.as_bytes();
+    match bytes[pos] {
+        b'\'' | b'"' | b'`' | b'[' | b'?' => true,
+        b'-' => bytes.get(pos + 1) == Some(&b'-'),
+        b'/' => bytes.get(pos + 1) == Some(&b'*'),
+        b':' | b'@' | b'$' => is_named(sql, pos),
+        _ => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Token::*;
+
+    fn all(sql: &str) -> Vec<Token<'_>> {
+        tokens(sql).collect()
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(all("a = ? AND b = ?12"), vec![Text("a = "), Param("?"), Text(" AND b = "), Param("?12")]);
+    }
+
+    #[test]
+    fn doubled_quotes_are_escapes() {
+        assert_eq!(all("'it''s ?' ?"), vec![Quoted("'it''s ?'"), Text(" "), Param("?")]);
+        assert_eq!(all(r#""a""b" ?"#), vec![Quoted(r#""a""b""#), Text(" "), Param("?")]);
+        assert_eq!(all("[a]]?"), vec![Quoted("[a]"), Text("]"), Param("?")]);
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(all("-- ?\n?"), vec![Comment("-- ?"), Text("\n"), Param("?")]);
+        assert_eq!(all("/* ? */?"), vec![Comment("/* ? */"), Param("?")]);
+        assert_eq!(all("/*/ ? */"), vec![Comment("/*/ ? */")]);
+        assert_eq!(all("a - b / c"), vec![Text("a - b / c")]);
+    }
+
+    #[test]
+    fn unterminated() {
+        assert_eq!(all("'abc"), vec![Quoted("'abc")]);
+        assert_eq!(all("/* abc"), vec![Comment("/* abc")]);
+        assert_eq!(all("-- abc"), vec![Comment("-- abc")]);
+    }
+
+    #[test]
+    fn named_parameters() {
+        assert_eq!(all(":a @b_1 $c"), vec![Named(":a"), Text(" "), Named("@b_1"), Text(" "), Named("$c")]);
+        assert_eq!(all("a$b :1 @ x"), vec![Text("a$b :1 @ x")]);
+    }
+
+    #[test]
+    fn non_ascii_text() {
+        assert_eq!(all("'é' é ?"), vec![Quoted("'é'"), Text(" é "), Param("?")]);
+    }
+}
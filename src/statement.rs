@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use serde::de::DeserializeOwned;
+use crate::connect::Connection;
+use crate::cursor::Cursor;
+use crate::error::RqliteError;
+use crate::options::QueryOptions;
+use crate::row::Row;
+use crate::sql::{tokens, Token};
+use crate::types::{into_params, IntoParams};
+
+/// Sql statement prepared with [`Connection::prepare()`]
+///
+/// The number of parameters is checked before sending the statement.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// let conn = ConnectOptions::new("127.0.0.1", 4001)
+///        .connect().await?;
+/// let insert = conn.prepare("INSERT INTO foo(name, age) VALUES (?, ?)")?;
+/// for (name, age) in [("fiona", 5), ("fred", 7)] {
+///     insert.execute(par!(name, age)).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Statement<'c> {
+    connection: &'c Connection,
+    prepared: Arc<Prepared>
+}
+
+/// Parsed statement, shared by statements prepared from the same sql
+#[derive(Debug)]
+pub(crate) struct Prepared {
+    /// Query string, already in json
+    sql: serde_json::Value,
+    params: usize
+}
+
+impl Prepared {
+    fn new(sql: &str) -> Result<Prepared, RqliteError> {
+        Ok(Prepared { sql: serde_json::Value::String(sql.to_owned()), params: placeholders(sql)? })
+    }
+}
+
+impl<'c> Statement<'c> {
+    pub(crate) fn new(connection: &'c Connection, sql: &str) -> Result<Statement<'c>, RqliteError> {
+        let prepared = connection.statements.get(sql)?;
+        Ok(Statement { connection, prepared })
+    }
+
+    /// Sql of the statement
+    pub fn sql(&self) -> &str {
+        self.prepared.sql.as_str().unwrap_or_default()
+    }
+
+    /// Number of parameters the statement expects
+    pub fn param_count(&self) -> usize {
+        self.prepared.params
+    }
+
+    /// Execute the statement as a write (`/db/execute` endpoint)
+    ///
+    /// Returns `RqliteError::SqlError` if the number of parameters is wrong, or RqliteError if the request fails
+    pub async fn execute(&self, params: impl IntoParams) -> Result<Cursor<'c>, RqliteError> {
+        let statement = self.bind(params)?;
+        let mut cursor = self.connection.cursor();
+        cursor.execute_prepared(statement).await?;
+        Ok(cursor)
+    }
+
+    /// Run the statement as a read (`/db/query` endpoint)
+    ///
+    /// Returns `RqliteError::SqlError` if the number of parameters is wrong, or RqliteError if the request fails
+    pub async fn query(&self, params: impl IntoParams) -> Result<Cursor<'c>, RqliteError> {
+        self.query_with(params, &QueryOptions::new()).await
+    }
+
+    /// Run the statement as a read with options
+    ///
+    /// See [`Statement::query()`]
+    pub async fn query_with(&self, params: impl IntoParams, options: &QueryOptions) -> Result<Cursor<'c>, RqliteError> {
+        let statement = self.bind(params)?;
+        let mut cursor = self.connection.cursor();
+        cursor.query_prepared(statement, options).await?;
+        Ok(cursor)
+    }
+
+    /// Run the statement as a read and get all rows
+    ///
+    /// See [`Statement::query()`]
+    pub async fn fetch_all(&self, params: impl IntoParams) -> Result<Vec<Row>, RqliteError> {
+        Ok(self.query(params).await?.fetch_all())
+    }
+
+    /// Run the statement as a read, it must return exactly one row
+    ///
+    /// See [`Statement::query()`]
+    pub async fn fetch_one(&self, params: impl IntoParams) -> Result<Row, RqliteError> {
+        self.query(params).await?.fetch_one()
+    }
+
+    /// Run the statement as a read, it must return at most one row
+    ///
+    /// See [`Statement::query()`]
+    pub async fn fetch_optional(&self, params: impl IntoParams) -> Result<Option<Row>, RqliteError> {
+        self.query(params).await?.fetch_optional()
+    }
+
+    /// Run the statement as a read and deserialize all rows into `T`
+    ///
+    /// See [`Cursor::fetch_all_as()`]
+    pub async fn query_as<T: DeserializeOwned>(&self, params: impl IntoParams) -> Result<Vec<T>, RqliteError> {
+        self.query(params).await?.fetch_all_as()
+    }
+
+    /// Build the json representation of the statement with `params`
+    fn bind(&self, params: impl IntoParams) -> Result<serde_json::Value, RqliteError> {
+        let params = into_params(params)?;
+        if params.len() != self.prepared.params {
+            return Err(RqliteError::SqlError(format!("Statement expects {} parameters, got {}", self.prepared.params, params.len())));
+        }
+        if params.is_empty() {
+            return Ok(self.prepared.sql.clone());
+        }
+        let mut vec = Vec::with_capacity(params.len() + 1);
+        vec.push(self.prepared.sql.clone());
+        vec.extend(params);
+        Ok(serde_json::Value::Array(vec))
+    }
+}
+
+/// Least recently used prepared statements of a connection, shared between clones
+#[derive(Debug)]
+pub(crate) struct StatementCache {
+    capacity: usize,
+    state: Mutex<CacheState>
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// Statements by sql, with the tick of their last use
+    entries: HashMap<String, (Arc<Prepared>, u64)>,
+    tick: u64
+}
+
+impl StatementCache {
+    pub(crate) fn new(capacity: usize) -> StatementCache {
+        StatementCache { capacity, state: Mutex::new(CacheState::default()) }
+    }
+
+    /// Get the prepared statement for `sql`, preparing it if it is not cached
+    fn get(&self, sql: &str) -> Result<Arc<Prepared>, RqliteError> {
+        if self.capacity == 0 {
+            return Ok(Arc::new(Prepared::new(sql)?));
+        }
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        if let Some((prepared, used)) = state.entries.get_mut(sql) {
+            *used = tick;
+            return Ok(prepared.clone());
+        }
+        let prepared = Arc::new(Prepared::new(sql)?);
+        if state.entries.len() >= self.capacity {
+            let oldest = state.entries.iter().min_by_key(|(_, (_, used))| *used).map(|(sql, _)| sql.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.entries.insert(sql.to_owned(), (prepared.clone(), tick));
+        Ok(prepared)
+    }
+}
+
+/// Number of parameters of `sql`, `?NNN` placeholders count up to their number
+///
+/// Returns `RqliteError::SqlError` for named parameters
+fn placeholders(sql: &str) -> Result<usize, RqliteError> {
+    let mut count = 0;
+    for token in tokens(sql) {
+        match token {
+            Token::Param(param) => match param[1..].parse::<usize>() {
+                Ok(n) => count = count.max(n),
+                Err(_) => count += 1
+            },
+            Token::Named(_) => {
+                return Err(RqliteError::SqlError("Named parameters are not supported by prepared statements".to_owned()));
+            },
+            _ => ()
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_placeholders() {
+        assert_eq!(placeholders("SELECT ?1, ?2").unwrap(), 2);
+        assert_eq!(placeholders("SELECT ?3").unwrap(), 3);
+        assert_eq!(placeholders("SELECT ?1, ?1").unwrap(), 1);
+    }
+
+    #[test]
+    fn bare_and_numbered_placeholders() {
+        assert_eq!(placeholders("SELECT ?, ?").unwrap(), 2);
+        // a bare ? takes the number following the largest one before it
+        assert_eq!(placeholders("SELECT ?5, ?").unwrap(), 6);
+        assert_eq!(placeholders("SELECT ?, ?1").unwrap(), 1);
+        assert_eq!(placeholders("SELECT ?, ?5, ?").unwrap(), 6);
+    }
+
+    #[test]
+    fn placeholders_in_strings_and_comments() {
+        assert_eq!(placeholders("SELECT '?', \"?\", `?`, [?], ?").unwrap(), 1);
+        assert_eq!(placeholders("SELECT 'it''s ?', ?").unwrap(), 1);
+        assert_eq!(placeholders("SELECT \"a\"\"?\" FROM foo WHERE id = ?").unwrap(), 1);
+        assert_eq!(placeholders("SELECT ? -- ?\n, ? /* ?, ?3 */").unwrap(), 2);
+    }
+
+    #[test]
+    fn named_parameters_are_rejected() {
+        for sql in &["SELECT :name", "SELECT @name", "SELECT $name", "SELECT ?, :_a"] {
+            assert!(matches!(placeholders(sql), Err(RqliteError::SqlError(_))), "{}", sql);
+        }
+        assert_eq!(placeholders("SELECT ':name', a$b, ? -- @name").unwrap(), 1);
+    }
+}
@@ -0,0 +1,72 @@
+//! Metrics emitted through the `metrics` facade, with the `metrics` feature
+//!
+//! - `rqlite_requests_total` (counter): http requests by `endpoint` and `status` (`error` if none)
+//! - `rqlite_request_duration_seconds` (histogram): time to get a response, by `endpoint`
+//! - `rqlite_errors_total` (counter): failed statements by `class`
+//! - `rqlite_rows_returned_total` (counter): rows returned by queries
+use hyper::{Body, Request, Response};
+use crate::error::RqliteError;
+
+/// Measures a request from sending to getting the response headers
+#[cfg(feature = "metrics")]
+pub(crate) struct RequestTimer {
+    endpoint: String,
+    start: std::time::Instant
+}
+
+#[cfg(feature = "metrics")]
+impl RequestTimer {
+    pub(crate) fn start(req: &Request<Body>) -> RequestTimer {
+        RequestTimer { endpoint: req.uri().path().to_owned(), start: std::time::Instant::now() }
+    }
+
+    pub(crate) fn finish(self, result: &Result<Response<Body>, RqliteError>) {
+        let status = match result {
+            Ok(resp) => resp.status().as_str().to_owned(),
+            Err(_) => "error".to_owned()
+        };
+        metrics::histogram!("rqlite_request_duration_seconds", "endpoint" => self.endpoint.clone())
+                .record(self.start.elapsed().as_secs_f64());
+        metrics::counter!("rqlite_requests_total", "endpoint" => self.endpoint, "status" => status)
+                .increment(1);
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn error(error: &RqliteError) {
+    let class = match error {
+        e if e.is_auth() => "auth",
+        RqliteError::Connection(_) | RqliteError::Http(_) | RqliteError::Dns { .. } | RqliteError::Tcp { .. } | RqliteError::Handshake(_) => "connection",
+        RqliteError::Tls(_) => "tls",
+        RqliteError::Timeout => "timeout",
+        RqliteError::StaleRead => "stale",
+        e if e.is_not_leader() => "leadership",
+        RqliteError::SqlError(_) => "sql",
+        RqliteError::Status { .. } => "status",
+        _ => "other"
+    };
+    metrics::counter!("rqlite_errors_total", "class" => class).increment(1);
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn rows_returned(rows: usize) {
+    metrics::counter!("rqlite_rows_returned_total").increment(rows as u64);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) struct RequestTimer;
+
+#[cfg(not(feature = "metrics"))]
+impl RequestTimer {
+    pub(crate) fn start(_req: &Request<Body>) -> RequestTimer {
+        RequestTimer
+    }
+
+    pub(crate) fn finish(self, _result: &Result<Response<Body>, RqliteError>) {}
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn error(_error: &RqliteError) {}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn rows_returned(_rows: usize) {}
@@ -0,0 +1,460 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use futures_core::Stream;
+use hyper::Body;
+use crate::cursor::result_error;
+use crate::error::RqliteError;
+use crate::row::Row;
+use crate::types::{ColumnTypes, Type};
+
+/// Rows of a query, parsed as the response arrives
+///
+/// Created by [`Connection::query_stream()`](crate::Connection::query_stream),
+/// only the rows not read yet are kept in memory.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// use futures::StreamExt;
+///
+/// let mut rows = conn.query_stream("SELECT * FROM foo", par!()).await?;
+/// while let Some(row) = rows.next().await {
+///     let name: String = row?.get(1)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RowStream {
+    body: Body,
+    parser: Parser,
+    done: bool
+}
+
+impl RowStream {
+    pub(crate) fn new(body: Body) -> RowStream {
+        RowStream { body, parser: Parser::default(), done: false }
+    }
+
+    /// Column names, known once the first rows arrived
+    pub fn columns(&self) -> Option<&[String]> {
+        self.parser.columns.as_deref().map(|columns| columns.as_slice())
+    }
+
+    /// Stop at the first error
+    fn fail(&mut self, error: RqliteError) -> Poll<Option<Result<Row, RqliteError>>> {
+        self.done = true;
+        self.parser.rows.clear();
+        Poll::Ready(Some(Err(error)))
+    }
+}
+
+impl Stream for RowStream {
+    type Item = Result<Row, RqliteError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(error) = this.parser.error.take() {
+                return this.fail(result_error(error));
+            }
+            if let Some(row) = this.parser.next_row() {
+                return Poll::Ready(Some(Ok(row)));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            match Pin::new(&mut this.body).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    if let Err(e) = this.parser.feed(&chunk) {
+                        return this.fail(e);
+                    }
+                },
+                Poll::Ready(Some(Err(e))) => return this.fail(RqliteError::Http(e)),
+                Poll::Ready(None) => {
+                    this.done = true;
+                    if !this.parser.complete() {
+                        return this.fail(RqliteError::DataSer("Response ended before the end of the json".to_owned()));
+                    }
+                },
+                Poll::Pending => return Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Object,
+    Array
+}
+
+#[derive(Debug)]
+struct Frame {
+    kind: Kind,
+    /// Key of the current member of an object
+    key: Option<String>,
+    /// Next string of an object is a key
+    expect_key: bool,
+    /// Array of rows of the first result
+    values: bool
+}
+
+/// Incremental parser of a query response
+///
+/// Bytes are scanned as they arrive, members of the first result and its rows
+/// are parsed by serde once complete and dropped from the buffer.
+#[derive(Debug, Default)]
+struct Parser {
+    buf: Vec<u8>,
+    /// Position of the next byte to scan in `buf`
+    pos: usize,
+    stack: Vec<Frame>,
+    in_string: bool,
+    escape: bool,
+    /// Start of the object key being read
+    key_start: Option<usize>,
+    /// Start and depth of the value being captured
+    capture: Option<(usize, usize)>,
+    /// Number of results started
+    results: usize,
+    columns: Option<Arc<Vec<String>>>,
+    types: Option<Arc<Vec<Type>>>,
+    error: Option<String>,
+    rows: VecDeque<Vec<serde_json::Value>>
+}
+
+impl Parser {
+    fn feed(&mut self, chunk: &[u8]) -> Result<(), RqliteError> {
+        self.buf.extend_from_slice(chunk);
+        while self.pos < self.buf.len() {
+            self.scan(self.buf[self.pos])?;
+            self.pos += 1;
+        }
+        // drop what has been parsed
+        let keep = [self.capture.map(|(start, _)| start), self.key_start].iter().flatten().copied().min().unwrap_or(self.pos);
+        self.buf.drain(..keep);
+        self.pos -= keep;
+        self.capture = self.capture.map(|(start, depth)| (start - keep, depth));
+        self.key_start = self.key_start.map(|start| start - keep);
+        Ok(())
+    }
+
+    fn scan(&mut self, c: u8) -> Result<(), RqliteError> {
+        if self.in_string {
+            if self.escape {
+                self.escape = false;
+            } else if c == b'\\' {
+                self.escape = true;
+            } else if c == b'"' {
+                self.in_string = false;
+                return self.string_end();
+            }
+            return Ok(());
+        }
+        match c {
+            b' ' | b'\t' | b'\r' | b'\n' | b':' => Ok(()),
+            b',' => {
+                self.scalar_end()?;
+                if let Some(frame) = self.stack.last_mut() {
+                    frame.expect_key = frame.kind == Kind::Object;
+                }
+                Ok(())
+            },
+            b'}' | b']' => {
+                self.scalar_end()?;
+                self.stack.pop();
+                match self.capture {
+                    Some((start, depth)) if depth == self.stack.len() => {
+                        self.capture = None;
+                        self.captured(start, self.pos + 1)
+                    },
+                    _ => Ok(())
+                }
+            },
+            b'"' if self.stack.last().is_some_and(|f| f.kind == Kind::Object && f.expect_key) => {
+                self.in_string = true;
+                self.key_start = Some(self.pos);
+                Ok(())
+            },
+            c => {
+                self.value_start(c);
+                match c {
+                    b'"' => self.in_string = true,
+                    b'{' => self.stack.push(Frame { kind: Kind::Object, key: None, expect_key: true, values: false }),
+                    b'[' => {
+                        let values = self.results == 1 && self.stack.len() == 3 && self.key(2) == Some("values");
+                        self.stack.push(Frame { kind: Kind::Array, key: None, expect_key: false, values });
+                    },
+                    _ => ()
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Key of the current member of the object at `depth`
+    fn key(&self, depth: usize) -> Option<&str> {
+        self.stack.get(depth).and_then(|frame| frame.key.as_deref())
+    }
+
+    /// Start capturing the value starting with `c` if it is a member of the first result or one of its rows
+    fn value_start(&mut self, c: u8) {
+        if self.capture.is_some() {
+            return;
+        }
+        let depth = self.stack.len();
+        if depth == 2 && c == b'{' && self.key(0) == Some("results") {
+            self.results += 1;
+        }
+        let member = depth == 3 && self.results == 1 && self.key(2) != Some("values");
+        let row = depth == 4 && self.results == 1 && self.stack[3].values;
+        let error = depth == 1 && self.key(0) == Some("error");
+        if member || row || error {
+            self.capture = Some((self.pos, depth));
+        }
+    }
+
+    fn string_end(&mut self) -> Result<(), RqliteError> {
+        if let Some(start) = self.key_start.take() {
+            let key: String = parse(&self.buf[start..=self.pos])?;
+            if let Some(frame) = self.stack.last_mut() {
+                frame.key = Some(key);
+                frame.expect_key = false;
+            }
+            return Ok(());
+        }
+        match self.capture {
+            Some((start, depth)) if depth == self.stack.len() => {
+                self.capture = None;
+                self.captured(start, self.pos + 1)
+            },
+            _ => Ok(())
+        }
+    }
+
+    /// End of a captured number or literal
+    fn scalar_end(&mut self) -> Result<(), RqliteError> {
+        match self.capture {
+            Some((start, depth)) if depth == self.stack.len() => {
+                self.capture = None;
+                self.captured(start, self.pos)
+            },
+            _ => Ok(())
+        }
+    }
+
+    /// Parse the captured value `buf[start..end]`
+    fn captured(&mut self, start: usize, end: usize) -> Result<(), RqliteError> {
+        let slice = &self.buf[start..end];
+        let depth = self.stack.len();
+        if depth == 4 {
+            self.rows.push_back(parse(slice)?);
+            return Ok(());
+        }
+        let key = if depth == 1 { self.key(0) } else { self.key(2) };
+        match key {
+            Some("columns") => self.columns = Some(Arc::new(parse(slice)?)),
+            Some("types") => {
+                if let ColumnTypes::List(types) = parse(slice)? {
+                    self.types = Some(Arc::new(types));
+                }
+            },
+            Some("error") => self.error = Some(parse(slice)?),
+            _ => ()
+        }
+        Ok(())
+    }
+
+    /// Next row, once the columns are known
+    fn next_row(&mut self) -> Option<Row> {
+        let columns = self.columns.clone()?;
+        let row = self.rows.pop_front()?;
+        let types = self.types.get_or_insert_with(|| Arc::new(vec![Type::Null; columns.len()])).clone();
+        Some(Row::new(columns, types, row))
+    }
+
+    /// Check the whole json has been read
+    fn complete(&self) -> bool {
+        self.stack.is_empty() && !self.in_string && self.capture.is_none()
+    }
+}
+
+fn parse<'a, T: serde::Deserialize<'a>>(slice: &'a [u8]) -> Result<T, RqliteError> {
+    serde_json::from_slice(slice).map_err(RqliteError::Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// Query response with strings full of json syntax, escapes and numbers of every form
+    const RESPONSE: &str = r#"{"results": [{
+        "columns": ["id", "name", "score", "data"],
+        "types": ["integer", "text", "real", "text"],
+        "values": [
+            [1, "fiona", -1.5e3, "{\"a\": [1, 2]}"],
+            [22, "say \"hi\", ok", 0.25, "\\"],
+            [-333, "]}[{,:", 12345678901, null],
+            [4, "café \\\" ☺", 1E-7, "values"]
+        ],
+        "time": 0.000123
+    }], "time": 0.0002}"#;
+
+    /// Rows, columns and error parsed from `chunks`
+    fn parse(chunks: &[&[u8]]) -> (Option<Vec<String>>, Vec<Vec<Value>>, Option<String>) {
+        let mut parser = Parser::default();
+        let mut rows = Vec::new();
+        for chunk in chunks {
+            parser.feed(chunk).unwrap();
+            rows.extend(parser.rows.drain(..));
+        }
+        assert!(parser.complete());
+        (parser.columns.as_deref().cloned(), rows, parser.error.take())
+    }
+
+    fn expected() -> (Option<Vec<String>>, Vec<Vec<Value>>, Option<String>) {
+        let json: Value = serde_json::from_str(RESPONSE).unwrap();
+        let result = &json["results"][0];
+        let columns = serde_json::from_value(result["columns"].clone()).unwrap();
+        let rows = serde_json::from_value(result["values"].clone()).unwrap();
+        (Some(columns), rows, None)
+    }
+
+    #[test]
+    fn whole_response() {
+        let parsed = parse(&[RESPONSE.as_bytes()]);
+        assert_eq!(parsed, expected());
+        assert_eq!(parsed.1.len(), 4);
+        assert_eq!(parsed.1[1][1], json!("say \"hi\", ok"));
+    }
+
+    #[test]
+    fn split_at_every_offset() {
+        let bytes = RESPONSE.as_bytes();
+        let expected = expected();
+        for i in 0..=bytes.len() {
+            assert_eq!(parse(&[&bytes[..i], &bytes[i..]]), expected, "split at {}", i);
+        }
+    }
+
+    #[test]
+    fn split_at_every_pair_of_offsets() {
+        let bytes = RESPONSE.as_bytes();
+        let expected = expected();
+        for i in 0..=bytes.len() {
+            for j in i..=bytes.len() {
+                assert_eq!(parse(&[&bytes[..i], &bytes[i..j], &bytes[j..]]), expected, "split at {} and {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn byte_by_byte() {
+        let chunks: Vec<&[u8]> = RESPONSE.as_bytes().chunks(1).collect();
+        assert_eq!(parse(&chunks), expected());
+    }
+
+    #[test]
+    fn types_of_first_result() {
+        let mut parser = Parser::default();
+        parser.feed(RESPONSE.as_bytes()).unwrap();
+        assert!(matches!(parser.types.as_deref().map(Vec::as_slice), Some([Type::Integer, Type::Text, Type::Real, Type::Text])));
+    }
+
+    #[test]
+    fn only_rows_of_first_result() {
+        let response = r#"{"results": [
+            {"columns": ["a"], "types": ["integer"], "values": [[1], [2]]},
+            {"columns": ["b", "c"], "types": ["text", "text"], "values": [["x", "y"]]},
+            {"error": "no such table: foo"}
+        ]}"#;
+        let bytes = response.as_bytes();
+        for i in 0..=bytes.len() {
+            let (columns, rows, error) = parse(&[&bytes[..i], &bytes[i..]]);
+            assert_eq!(columns, Some(vec!["a".to_owned()]), "split at {}", i);
+            assert_eq!(rows, vec![vec![json!(1)], vec![json!(2)]], "split at {}", i);
+            assert_eq!(error, None, "split at {}", i);
+        }
+    }
+
+    #[test]
+    fn result_error() {
+        let response = r#"{"results": [{"error": "near \"SELEC\": syntax error"}]}"#;
+        let bytes = response.as_bytes();
+        for i in 0..=bytes.len() {
+            let (columns, rows, error) = parse(&[&bytes[..i], &bytes[i..]]);
+            assert_eq!((columns, rows.len()), (None, 0), "split at {}", i);
+            assert_eq!(error.as_deref(), Some("near \"SELEC\": syntax error"), "split at {}", i);
+        }
+    }
+
+    #[test]
+    fn top_level_error() {
+        let response = r#"{"error": "stale read", "results": []}"#;
+        let bytes = response.as_bytes();
+        for i in 0..=bytes.len() {
+            let (_, rows, error) = parse(&[&bytes[..i], &bytes[i..]]);
+            assert!(rows.is_empty(), "split at {}", i);
+            assert_eq!(error.as_deref(), Some("stale read"), "split at {}", i);
+        }
+    }
+
+    #[test]
+    fn empty_result() {
+        let (columns, rows, error) = parse(&[br#"{"results": [{"columns": ["a"], "types": ["integer"]}]}"#]);
+        assert_eq!((columns, rows.len(), error), (Some(vec!["a".to_owned()]), 0, None));
+    }
+
+    #[test]
+    fn incomplete_response() {
+        let mut parser = Parser::default();
+        parser.feed(&RESPONSE.as_bytes()[..RESPONSE.len() / 2]).unwrap();
+        assert!(!parser.complete());
+    }
+
+    /// Stream of `chunks` sent one by one
+    async fn stream(chunks: Vec<&'static str>) -> Vec<Result<Row, RqliteError>> {
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            for chunk in chunks {
+                if sender.send_data(chunk.into()).await.is_err() {
+                    return;
+                }
+            }
+        });
+        let mut rows = RowStream::new(body);
+        let mut items = Vec::new();
+        while let Some(item) = std::future::poll_fn(|cx| Pin::new(&mut rows).poll_next(cx)).await {
+            items.push(item);
+        }
+        items
+    }
+
+    #[tokio::test]
+    async fn row_stream() {
+        let (head, tail) = RESPONSE.split_at(RESPONSE.find("fiona").unwrap() + 2);
+        let items = stream(vec![head, tail]).await;
+        let names: Vec<String> = items.into_iter().map(|row| row.unwrap().get(1).unwrap()).collect();
+        assert_eq!(names, ["fiona", "say \"hi\", ok", "]}[{,:", "caf\u{e9} \\\" \u{263a}"]);
+    }
+
+    #[tokio::test]
+    async fn row_stream_errors() {
+        let items = stream(vec![r#"{"results": [{"error": "no such table: foo"}]}"#]).await;
+        assert!(matches!(items.as_slice(), [Err(RqliteError::SqlError(e))] if e == "no such table: foo"));
+
+        let items = stream(vec![r#"{"results": [{"error": "stale read"}]}"#]).await;
+        assert!(matches!(items.as_slice(), [Err(RqliteError::StaleRead)]));
+
+        let items = stream(vec![r#"{"results": [{"columns": ["a"], "types": ["integer"], "values": [[1], [2"#]).await;
+        assert!(matches!(items.as_slice(), [Ok(_), Err(RqliteError::DataSer(_))]));
+
+        let items = stream(vec![r#"{"results": [{"columns": ["a"], "values": [[1], [tru"#]).await;
+        assert!(matches!(items.last(), Some(Err(_))));
+    }
+}
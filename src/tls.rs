@@ -0,0 +1,146 @@
+//! Tls backends
+//!
+//! native-tls is used by default, rustls when the `rustls` feature is enabled.
+
+use std::error::Error;
+use crate::connect::{ConnectOptions, Socket};
+
+/// PEM encoded root certificates to trust
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+async fn ca_certs(options: &ConnectOptions) -> Result<Vec<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+    use crate::connect::CaCert;
+
+    let mut certs = Vec::with_capacity(options.ca_certs.len());
+    for cert in &options.ca_certs {
+        certs.push(match cert {
+            CaCert::Pem(pem)   => pem.clone(),
+            CaCert::File(path) => tokio::fs::read(path).await?
+        });
+    }
+    Ok(certs)
+}
+
+/// Run tls handshake over `sock` using native-tls
+///
+/// Returns the stream and whether HTTP/2 was negotiated
+#[cfg(all(feature = "native-tls", not(feature = "rustls")))]
+pub(crate) async fn connect(options: &ConnectOptions, sock: Box<dyn Socket>) -> Result<(Box<dyn Socket>, bool), Box<dyn Error + Send + Sync>> {
+    use tokio_native_tls::native_tls::{Certificate, Identity, TlsConnector};
+
+    let mut builder = TlsConnector::builder();
+    builder.danger_accept_invalid_certs(options.accept_invalid_cert)
+           .danger_accept_invalid_hostnames(options.accept_invalid_cert);
+    for pem in ca_certs(options).await? {
+        builder.add_root_certificate(Certificate::from_pem(&pem)?);
+    }
+    if let Some(id) = &options.client_identity {
+        builder.identity(match id.is_pem() {
+            true  => Identity::from_pkcs8(&id.identity, &id.identity)?,
+            false => Identity::from_pkcs12(&id.identity, &id.password)?
+        });
+    }
+    if options.http2 {
+        builder.request_alpns(&["h2", "http/1.1"]);
+    }
+    let builder = builder.build()?;
+    let cx = tokio_native_tls::TlsConnector::from(builder);
+
+    let stream = cx.connect(&options.host, sock).await?;
+    let h2 = stream.get_ref().negotiated_alpn()?.as_deref() == Some(b"h2");
+    Ok((Box::new(stream), h2))
+}
+
+/// Run tls handshake over `sock` using rustls
+///
+/// Returns the stream and whether HTTP/2 was negotiated
+#[cfg(feature = "rustls")]
+pub(crate) async fn connect(options: &ConnectOptions, sock: Box<dyn Socket>) -> Result<(Box<dyn Socket>, bool), Box<dyn Error + Send + Sync>> {
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+    use tokio_rustls::rustls::{self, ClientConfig, RootCertStore, ServerName};
+
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_native_certs::load_native_certs()? {
+        roots.add(&rustls::Certificate(cert.0))?;
+    }
+    for pem in ca_certs(options).await? {
+        for cert in rustls_pemfile::certs(&mut pem.as_slice())? {
+            roots.add(&rustls::Certificate(cert))?;
+        }
+    }
+    let builder = ClientConfig::builder()
+                            .with_safe_defaults()
+                            .with_root_certificates(roots);
+    let mut config = match &options.client_identity {
+        Some(id) => {
+            if !id.is_pem() {
+                return Err("PKCS#12 client identity requires the native-tls feature, use PEM with rustls".into());
+            }
+            let (certs, key) = pem_identity(&id.identity)?;
+            builder.with_client_auth_cert(certs, key)?
+        },
+        None => builder.with_no_client_auth()
+    };
+    if options.accept_invalid_cert {
+        config.dangerous().set_certificate_verifier(Arc::new(danger::NoVerifier));
+    }
+    if options.http2 {
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    }
+    let cx = tokio_rustls::TlsConnector::from(Arc::new(config));
+
+    let stream = cx.connect(ServerName::try_from(options.host.as_str())?, sock).await?;
+    let h2 = stream.get_ref().1.alpn_protocol() == Some(b"h2");
+    Ok((Box::new(stream), h2))
+}
+
+/// Certificate chain and private key of a PEM encoded client identity
+#[cfg(feature = "rustls")]
+fn pem_identity(pem: &[u8]) -> Result<(Vec<tokio_rustls::rustls::Certificate>, tokio_rustls::rustls::PrivateKey), Box<dyn Error + Send + Sync>> {
+    use rustls_pemfile::Item;
+    use tokio_rustls::rustls::{Certificate, PrivateKey};
+
+    let mut certs = Vec::new();
+    let mut key = None;
+    for item in rustls_pemfile::read_all(&mut &pem[..])? {
+        match item {
+            Item::X509Certificate(cert) => certs.push(Certificate(cert)),
+            Item::PKCS8Key(k) | Item::RSAKey(k) | Item::ECKey(k) => key = Some(PrivateKey(k)),
+            _ => {}
+        }
+    }
+    match key {
+        Some(key) => Ok((certs, key)),
+        None => Err("client identity has no private key".into())
+    }
+}
+
+/// No tls backend enabled, https is unavailable
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+pub(crate) async fn connect(_options: &ConnectOptions, _sock: Box<dyn Socket>) -> Result<(Box<dyn Socket>, bool), Box<dyn Error + Send + Sync>> {
+    Err("https requires the native-tls or rustls feature".into())
+}
+
+#[cfg(feature = "rustls")]
+mod danger {
+    use std::time::SystemTime;
+    use tokio_rustls::rustls::{self, Certificate, ServerName};
+    use tokio_rustls::rustls::client::{ServerCertVerified, ServerCertVerifier};
+
+    /// Accept any certificate, used for `accept_invalid_cert`
+    pub(super) struct NoVerifier;
+
+    impl ServerCertVerifier for NoVerifier {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &Certificate,
+            _intermediates: &[Certificate],
+            _server_name: &ServerName,
+            _scts: &mut dyn Iterator<Item = &[u8]>,
+            _ocsp_response: &[u8],
+            _now: SystemTime
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+    }
+}
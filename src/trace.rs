@@ -0,0 +1,117 @@
+//! Spans for queries and requests, created with the `tracing` feature
+use std::future::Future;
+use hyper::{Body, Request, Response};
+use crate::connect::ConnectOptions;
+use crate::error::RqliteError;
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::Span;
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Span;
+
+/// Span of a http request, the node is recorded once known
+#[cfg(feature = "tracing")]
+pub(crate) fn request_span(req: &Request<Body>) -> Span {
+    use tracing::field::Empty;
+    tracing::info_span!("rqlite.request",
+        http.method = %req.method(),
+        http.target = req.uri().path(),
+        net.peer.name = Empty,
+        http.status_code = Empty,
+        latency_ms = Empty,
+        error = Empty)
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn record_node(span: &Span, host: &str, port: u16) {
+    span.record("net.peer.name", tracing::field::display(crate::proxy::authority(host, port)));
+}
+
+/// Run `fut` in the request span, recording status and latency
+#[cfg(feature = "tracing")]
+pub(crate) async fn in_request<F>(span: &Span, fut: F) -> Result<Response<Body>, RqliteError>
+where
+    F: Future<Output = Result<Response<Body>, RqliteError>>
+{
+    use tracing::Instrument;
+    let start = std::time::Instant::now();
+    let result = fut.instrument(span.clone()).await;
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+    match &result {
+        Ok(resp) => span.record("http.status_code", resp.status().as_u16()),
+        Err(e) => span.record("error", tracing::field::display(e))
+    };
+    result
+}
+
+/// Span of statements sent to `/db/execute` or `/db/query`
+///
+/// Statements are truncated to [`ConnectOptions::trace_statement_len()`]
+#[cfg(feature = "tracing")]
+pub(crate) fn query_span(uri: &str, statements: &serde_json::Value, options: &ConnectOptions) -> Span {
+    use tracing::field::Empty;
+    let mut statement = statements.as_array().into_iter().flatten()
+            .filter_map(|s| match s {
+                serde_json::Value::Array(v) => v.first().and_then(|v| v.as_str()),
+                v => v.as_str()
+            })
+            .collect::<Vec<&str>>()
+            .join("; ");
+    if let Some(len) = options.trace_statement_len {
+        if let Some((i, _)) = statement.char_indices().nth(len) {
+            statement.truncate(i);
+        }
+    }
+    tracing::info_span!("rqlite.query",
+        db.system = "rqlite",
+        db.statement = statement.as_str(),
+        db.operation = uri.split('?').next().unwrap_or(uri),
+        latency_ms = Empty,
+        error = Empty)
+}
+
+/// Run `fut` in the query span, recording latency and error
+#[cfg(feature = "tracing")]
+pub(crate) async fn in_query<T, F>(span: Span, fut: F) -> Result<T, RqliteError>
+where
+    F: Future<Output = Result<T, RqliteError>>
+{
+    use tracing::Instrument;
+    let start = std::time::Instant::now();
+    let result = fut.instrument(span.clone()).await;
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+    if let Err(e) = &result {
+        span.record("error", tracing::field::display(e));
+    }
+    result
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn request_span(_req: &Request<Body>) -> Span {
+    Span
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn record_node(_span: &Span, _host: &str, _port: u16) {}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn in_request<F>(_span: &Span, fut: F) -> Result<Response<Body>, RqliteError>
+where
+    F: Future<Output = Result<Response<Body>, RqliteError>>
+{
+    fut.await
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn query_span(_uri: &str, _statements: &serde_json::Value, _options: &ConnectOptions) -> Span {
+    Span
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn in_query<T, F>(_span: Span, fut: F) -> Result<T, RqliteError>
+where
+    F: Future<Output = Result<T, RqliteError>>
+{
+    fut.await
+}
@@ -0,0 +1,47 @@
+use std::io;
+use std::sync::Arc;
+use crate::connect::Socket;
+use crate::middleware::BoxFuture;
+
+/// Connector opening the byte stream to a node
+///
+/// Replaces tcp connections (and proxies), to use tunnels, custom sockets or in-memory
+/// pipes in tests. With [`Scheme::HTTPS`](crate::Scheme) the client negotiates tls over the
+/// returned stream, a transport doing tls itself should be used with `Scheme::HTTP`.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// use rqlite::{BoxFuture, Socket, Transport};
+///
+/// struct Tunnel { local_port: u16 }
+///
+/// impl Transport for Tunnel {
+///     fn connect<'a>(&'a self, _host: &'a str, _port: u16) -> BoxFuture<'a, std::io::Result<Box<dyn Socket>>> {
+///         Box::pin(async move {
+///             let sock = tokio::net::TcpStream::connect(("127.0.0.1", self.local_port)).await?;
+///             Ok(Box::new(sock) as Box<dyn Socket>)
+///         })
+///     }
+/// }
+///
+/// let conn = ConnectOptions::new("db.internal", 4001)
+///        .transport(Tunnel { local_port: 14001 })
+///        .connect().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Transport: Send + Sync + 'static {
+    /// Open a stream to `host:port`
+    fn connect<'a>(&'a self, host: &'a str, port: u16) -> BoxFuture<'a, io::Result<Box<dyn Socket>>>;
+}
+
+/// Transport of a connection
+#[derive(Clone)]
+pub(crate) struct CustomTransport(pub(crate) Arc<dyn Transport>);
+
+impl std::fmt::Debug for CustomTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Transport")
+    }
+}
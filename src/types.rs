@@ -1,4 +1,6 @@
-use serde::{Deserialize, Deserializer, de};
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize};
+use crate::error::RqliteError;
 
 /// Sqlite types
 #[repr(u8)]
@@ -8,56 +10,719 @@ pub enum Type {
     Integer,
     Real,
     Text,
-    Blob
-}
-
-/// Parse vector of json values
-pub fn parse_vec_types<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Type>>, D::Error> {
-    let s: Option<Vec<String>> = Option::deserialize(deserializer)?;
-    match s {
-        Some(s_vec) => {
-            let mut vec: Vec<Type> = Vec::with_capacity(s_vec.len());
-            for i in 0..s_vec.len() {
-                vec.push(match get_type(s_vec[i].as_str()) {
-                    Ok(val) => val,
-                    Err(_) => return Err(de::Error::custom(format!("Unknewn sqlite type {}", s_vec[i])))
-                });
+    Blob,
+    /// Declared types with numeric affinity, like `NUMERIC`, `BOOLEAN` or `DATETIME`
+    Numeric
+}
+
+/// Column types of a result
+#[derive(Debug)]
+pub(crate) enum ColumnTypes {
+    /// Types in the order of columns
+    List(Vec<Type>),
+    /// Types by column name, in associative results
+    Named(Vec<(String, Type)>)
+}
+
+impl<'de> Deserialize<'de> for ColumnTypes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ColumnTypes, D::Error> {
+        struct ColumnTypesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for ColumnTypesVisitor {
+            type Value = ColumnTypes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a list of types or a map of column names to types")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<ColumnTypes, A::Error> {
+                let mut types = Vec::new();
+                while let Some(_type) = seq.next_element::<String>()? {
+                    types.push(get_type(&_type));
+                }
+                Ok(ColumnTypes::List(types))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<ColumnTypes, A::Error> {
+                let mut types = Vec::new();
+                while let Some((name, _type)) = map.next_entry::<String, String>()? {
+                    types.push((name, get_type(&_type)));
+                }
+                Ok(ColumnTypes::Named(types))
             }
-            Ok(Some(vec))
         }
-        None => Ok(None)
+
+        deserializer.deserialize_any(ColumnTypesVisitor)
     }
 }
 
 /// Intepret type
-fn get_type(_type: &str) -> Result<Type, ()> {
-    Ok(match _type {
+///
+/// rqlite reports declared column types, they are read with the sqlite type affinity rules
+fn get_type(_type: &str) -> Type {
+    let upper = _type.to_ascii_uppercase();
+    match upper.as_str() {
         "" => Type::Null,
-        "integer" => Type::Integer,
-        "real" => Type::Real,
-        "text" => Type::Text,
-        "blob" => Type::Blob,
-        _ => return Err(())
-    })
+        t if t.contains("INT") => Type::Integer,
+        t if t.contains("CHAR") || t.contains("CLOB") || t.contains("TEXT") => Type::Text,
+        t if t.contains("BLOB") => Type::Blob,
+        t if t.contains("REAL") || t.contains("FLOA") || t.contains("DOUB") => Type::Real,
+        _ => Type::Numeric
+    }
+}
+
+/// Value passed to [`par!`](crate::par!) or [`named_par!`](crate::named_par!)
+#[doc(hidden)]
+pub struct Param<'a, T: ?Sized>(pub &'a T);
+
+/// Conversion of a value to a statement parameter
+///
+/// Used by [`par!`](crate::par!) instead of serde when implemented, and required for the elements
+/// of [`IntoParams`] tuples, arrays and vectors.
+pub trait ToParam {
+    /// Json representation of the parameter sent to rqlite
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error>;
+}
+
+macro_rules! to_param_from {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl ToParam for $t {
+                fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+                    Ok(serde_json::Value::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+to_param_from!(bool, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl ToParam for str {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        Ok(serde_json::Value::String(self.to_owned()))
+    }
+}
+
+impl ToParam for String {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        Ok(serde_json::Value::String(self.clone()))
+    }
+}
+
+impl ToParam for char {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        Ok(serde_json::Value::String(self.to_string()))
+    }
+}
+
+impl ToParam for serde_json::Value {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        Ok(self.clone())
+    }
+}
+
+/// Blobs are sent in base64, like rqlite returns them
+fn blob(bytes: &[u8]) -> Result<serde_json::Value, serde_json::Error> {
+    Ok(serde_json::Value::String(base64::encode(bytes)))
+}
+
+impl ToParam for [u8] {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        blob(self)
+    }
+}
+
+impl<const N: usize> ToParam for [u8; N] {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        blob(self)
+    }
+}
+
+impl ToParam for Vec<u8> {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        blob(self)
+    }
+}
+
+impl ToParam for Box<[u8]> {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        blob(self)
+    }
+}
+
+impl<T: ToParam + ?Sized> ToParam for &T {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        (**self).to_param()
+    }
+}
+
+impl<T: ToParam> ToParam for Option<T> {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        match self {
+            Some(v) => v.to_param(),
+            None => Ok(serde_json::Value::Null)
+        }
+    }
+}
+
+/// Value stored as json text, like columns used with the sqlite JSON1 functions
+///
+/// As a parameter the value is serialized to text, as a column the text is parsed.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// # #[derive(serde::Serialize, serde::Deserialize)]
+/// # struct Data { tags: Vec<String> }
+/// # let data = Data { tags: vec!["rust".to_owned()] };
+/// # let row = conn.fetch_one("SELECT data FROM foo", par!()).await?;
+/// conn.execute("INSERT INTO foo(data) VALUES (?)", par!(Json(&data))).await?;
+/// let data: Json<Data> = row.get(0)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> ToParam for Json<T> {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        Ok(serde_json::Value::String(serde_json::to_string(&self.0)?))
+    }
+}
+
+/// Json text, so [`Json`] fields are sent as text by [`Params::from_serialize()`]
+impl<T: Serialize> Serialize for Json<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let text = serde_json::to_string(&self.0).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&text)
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Json<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(text) => serde_json::from_str(&text),
+            // numbers and nulls are valid json as they are
+            value => serde_json::from_value(value)
+        };
+        value.map(Json).map_err(D::Error::custom)
+    }
+}
+
+/// Parameters of a statement
+///
+/// Implemented for the vector built by [`par!`](crate::par!), and for tuples, arrays, slices and vectors of [`ToParam`] values.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// conn.execute("INSERT INTO foo(name, age) VALUES (?, ?)", ("fiona", 5)).await?;
+/// conn.query("SELECT * FROM foo WHERE id IN (?, ?, ?)", [1, 2, 3]).await?;
+/// conn.query("SELECT * FROM foo", ()).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait IntoParams {
+    /// Json representation of the parameters, in order
+    fn into_params(self) -> Result<Vec<serde_json::Value>, serde_json::Error>;
+}
+
+impl IntoParams for () {
+    fn into_params(self) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+        Ok(Vec::new())
+    }
+}
+
+impl IntoParams for Vec<serde_json::Value> {
+    fn into_params(self) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+        Ok(self)
+    }
+}
+
+impl<T: ToParam> IntoParams for &[T] {
+    fn into_params(self) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+        self.iter().map(ToParam::to_param).collect()
+    }
+}
+
+impl<T: ToParam> IntoParams for &Vec<T> {
+    fn into_params(self) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+        self.as_slice().into_params()
+    }
+}
+
+impl<T: ToParam, const N: usize> IntoParams for [T; N] {
+    fn into_params(self) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+        self.iter().map(ToParam::to_param).collect()
+    }
+}
+
+impl<T: ToParam, const N: usize> IntoParams for &[T; N] {
+    fn into_params(self) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+        self.iter().map(ToParam::to_param).collect()
+    }
+}
+
+macro_rules! into_params_tuple {
+    ( $( $t:ident $i:tt ),+ ) => {
+        impl<$( $t: ToParam ),+> IntoParams for ($( $t, )+) {
+            fn into_params(self) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+                Ok(vec![$( self.$i.to_param()? ),+])
+            }
+        }
+    };
+}
+
+into_params_tuple!(A 0);
+into_params_tuple!(A 0, B 1);
+into_params_tuple!(A 0, B 1, C 2);
+into_params_tuple!(A 0, B 1, C 2, D 3);
+into_params_tuple!(A 0, B 1, C 2, D 3, E 4);
+into_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+into_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+into_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+into_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+into_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+into_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+into_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+/// Named parameters built from a serializable value
+/// ```no_run
+/// # use rqlite::*;
+/// # use serde::Serialize;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// #[derive(Serialize)]
+/// struct Foo {
+///     name: String,
+///     age: u8
+/// }
+///
+/// let foo = Foo { name: "fiona".to_owned(), age: 5 };
+/// conn.execute_named("INSERT INTO foo(name, age) VALUES (:name, :age)", Params::from_serialize(&foo)?).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Params;
+
+impl Params {
+    /// Named parameters from the fields of `value`, a struct or a map
+    ///
+    /// Fields serialized as bytes, like `bytes::Bytes` or `Vec<u8>` with `#[serde(with = "serde_bytes")]`,
+    /// are sent as blobs, other arrays and objects as json text.
+    ///
+    /// Returns `RqliteError::Json` if `value` cannot be serialized, `RqliteError::DataSer` if it is not a struct or a map
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<serde_json::Map<String, serde_json::Value>, RqliteError> {
+        match value.serialize(ParamsSerializer) {
+            Ok(map) => Ok(map),
+            Err(ParamsError::NotAMap) => Err(RqliteError::DataSer("Parameters must be a struct or a map".to_owned())),
+            Err(ParamsError::Json(e)) => Err(RqliteError::Json(e))
+        }
+    }
+}
+
+/// Nested values are not valid parameters, send them as json text
+fn nested_param(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => serde_json::Value::String(value.to_string()),
+        value => value
+    }
+}
+
+#[derive(Debug)]
+enum ParamsError {
+    NotAMap,
+    Json(serde_json::Error)
+}
+
+impl std::fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamsError::NotAMap => f.write_str("Parameters must be a struct or a map"),
+            ParamsError::Json(e) => e.fmt(f)
+        }
+    }
+}
+
+impl std::error::Error for ParamsError {}
+
+impl serde::ser::Error for ParamsError {
+    fn custom<M: std::fmt::Display>(msg: M) -> Self {
+        ParamsError::Json(serde::ser::Error::custom(msg))
+    }
+}
+
+/// Serializer of the struct or map given to [`Params::from_serialize()`]
+struct ParamsSerializer;
+
+macro_rules! not_a_map {
+    ( $( $name:ident ( $( $arg:ty ),* ) -> $ret:ty ; )* ) => {
+        $(
+            fn $name(self $( , _: $arg )*) -> Result<$ret, ParamsError> {
+                Err(ParamsError::NotAMap)
+            }
+        )*
+    };
+}
+
+type NotAMap = serde::ser::Impossible<serde_json::Map<String, serde_json::Value>, ParamsError>;
+
+impl serde::Serializer for ParamsSerializer {
+    type Ok = serde_json::Map<String, serde_json::Value>;
+    type Error = ParamsError;
+    type SerializeSeq = NotAMap;
+    type SerializeTuple = NotAMap;
+    type SerializeTupleStruct = NotAMap;
+    type SerializeTupleVariant = NotAMap;
+    type SerializeMap = ParamsMap;
+    type SerializeStruct = ParamsMap;
+    type SerializeStructVariant = NotAMap;
+
+    not_a_map! {
+        serialize_bool(bool) -> Self::Ok;
+        serialize_i8(i8) -> Self::Ok;
+        serialize_i16(i16) -> Self::Ok;
+        serialize_i32(i32) -> Self::Ok;
+        serialize_i64(i64) -> Self::Ok;
+        serialize_u8(u8) -> Self::Ok;
+        serialize_u16(u16) -> Self::Ok;
+        serialize_u32(u32) -> Self::Ok;
+        serialize_u64(u64) -> Self::Ok;
+        serialize_f32(f32) -> Self::Ok;
+        serialize_f64(f64) -> Self::Ok;
+        serialize_char(char) -> Self::Ok;
+        serialize_str(&str) -> Self::Ok;
+        serialize_bytes(&[u8]) -> Self::Ok;
+        serialize_none() -> Self::Ok;
+        serialize_unit() -> Self::Ok;
+        serialize_unit_struct(&'static str) -> Self::Ok;
+        serialize_unit_variant(&'static str, u32, &'static str) -> Self::Ok;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, ParamsError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<Self::Ok, ParamsError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _: &'static str, _: u32, _: &'static str, _: &T) -> Result<Self::Ok, ParamsError> {
+        Err(ParamsError::NotAMap)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<ParamsMap, ParamsError> {
+        Ok(ParamsMap { map: serde_json::Map::with_capacity(len.unwrap_or(0)), key: None })
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<ParamsMap, ParamsError> {
+        self.serialize_map(Some(len))
+    }
+}
+
+struct ParamsMap {
+    map: serde_json::Map<String, serde_json::Value>,
+    key: Option<String>
+}
+
+impl ParamsMap {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), ParamsError> {
+        let value = value.serialize(ParamSerializer).map_err(ParamsError::Json)?;
+        self.map.insert(key, nested_param(value));
+        Ok(())
+    }
+}
+
+impl serde::ser::SerializeMap for ParamsMap {
+    type Ok = serde_json::Map<String, serde_json::Value>;
+    type Error = ParamsError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ParamsError> {
+        // keys are converted like serde_json does, so numbers and booleans are allowed
+        let key = match serde_json::to_value(key).map_err(ParamsError::Json)? {
+            serde_json::Value::String(key) => key,
+            key @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_)) => key.to_string(),
+            _ => return Err(serde::ser::Error::custom("key must be a string"))
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ParamsError> {
+        let key = self.key.take().ok_or_else(|| serde::ser::Error::custom("value without a key"))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, ParamsError> {
+        Ok(self.map)
+    }
+}
+
+impl serde::ser::SerializeStruct for ParamsMap {
+    type Ok = serde_json::Map<String, serde_json::Value>;
+    type Error = ParamsError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), ParamsError> {
+        self.insert(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok, ParamsError> {
+        Ok(self.map)
+    }
+}
+
+/// Serializer of one parameter, like serde_json's except bytes are blobs
+struct ParamSerializer;
+
+type JsonSerializer = serde_json::value::Serializer;
+
+macro_rules! forward_to_json {
+    ( $( $name:ident ( $( $arg:ident : $ty:ty ),* ) -> $ret:ty ; )* ) => {
+        $(
+            fn $name(self $( , $arg: $ty )*) -> Result<$ret, serde_json::Error> {
+                serde_json::value::Serializer.$name($( $arg ),*)
+            }
+        )*
+    };
+}
+
+impl serde::Serializer for ParamSerializer {
+    type Ok = serde_json::Value;
+    type Error = serde_json::Error;
+    type SerializeSeq = <JsonSerializer as serde::Serializer>::SerializeSeq;
+    type SerializeTuple = <JsonSerializer as serde::Serializer>::SerializeTuple;
+    type SerializeTupleStruct = <JsonSerializer as serde::Serializer>::SerializeTupleStruct;
+    type SerializeTupleVariant = <JsonSerializer as serde::Serializer>::SerializeTupleVariant;
+    type SerializeMap = <JsonSerializer as serde::Serializer>::SerializeMap;
+    type SerializeStruct = <JsonSerializer as serde::Serializer>::SerializeStruct;
+    type SerializeStructVariant = <JsonSerializer as serde::Serializer>::SerializeStructVariant;
+
+    forward_to_json! {
+        serialize_bool(v: bool) -> Self::Ok;
+        serialize_i8(v: i8) -> Self::Ok;
+        serialize_i16(v: i16) -> Self::Ok;
+        serialize_i32(v: i32) -> Self::Ok;
+        serialize_i64(v: i64) -> Self::Ok;
+        serialize_i128(v: i128) -> Self::Ok;
+        serialize_u8(v: u8) -> Self::Ok;
+        serialize_u16(v: u16) -> Self::Ok;
+        serialize_u32(v: u32) -> Self::Ok;
+        serialize_u64(v: u64) -> Self::Ok;
+        serialize_u128(v: u128) -> Self::Ok;
+        serialize_f32(v: f32) -> Self::Ok;
+        serialize_f64(v: f64) -> Self::Ok;
+        serialize_char(v: char) -> Self::Ok;
+        serialize_str(v: &str) -> Self::Ok;
+        serialize_none() -> Self::Ok;
+        serialize_unit() -> Self::Ok;
+        serialize_unit_struct(name: &'static str) -> Self::Ok;
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str) -> Self::Ok;
+        serialize_seq(len: Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(len: usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(name: &'static str, len: usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(name: &'static str, index: u32, variant: &'static str, len: usize) -> Self::SerializeTupleVariant;
+        serialize_map(len: Option<usize>) -> Self::SerializeMap;
+        serialize_struct(name: &'static str, len: usize) -> Self::SerializeStruct;
+        serialize_struct_variant(name: &'static str, index: u32, variant: &'static str, len: usize) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, serde_json::Error> {
+        blob(v)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, serde_json::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<Self::Ok, serde_json::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, name: &'static str, index: u32, variant: &'static str, value: &T) -> Result<Self::Ok, serde_json::Error> {
+        serde_json::value::Serializer.serialize_newtype_variant(name, index, variant, value)
+    }
+}
+
+/// Convert `params`, serialization errors are returned as `RqliteError::Json`
+pub(crate) fn into_params(params: impl IntoParams) -> Result<Vec<serde_json::Value>, RqliteError> {
+    params.into_params().map_err(RqliteError::Json)
+}
+
+#[doc(hidden)]
+pub trait CustomParam {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error>;
+}
+
+impl<T: ToParam + ?Sized> CustomParam for Param<'_, T> {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        self.0.to_param()
+    }
+}
+
+/// Other values are serialized, only used when the value is not [`ToParam`] (autoref specialization)
+#[doc(hidden)]
+pub trait SerializeParam {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error>;
+}
+
+impl<T: Serialize + ?Sized> SerializeParam for &Param<'_, T> {
+    fn to_param(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(self.0)
+    }
+}
+
+/// Convert a parameter to json, with [`ToParam`] if implemented or serde
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __param {
+    ( $x:expr ) => {
+        {
+            #[allow(unused_imports)]
+            use $crate::__private::{CustomParam as _, SerializeParam as _};
+            (&$crate::__private::Param(&$x)).to_param()
+        }
+    };
 }
 
 /// Specify parameters for parameterized statements.
 ///
 /// Warning: Using raw queries may introduce vulnerabilities.
 ///
-/// Named parameters is not supported by rqlite.
-/// ```
-/// conn.execute("SELECT * FROM foo where name = ?", par!("fiona"))?;
+/// Byte buffers like `Vec<u8>` or `&[u8]` are sent as blobs, [`Json`] values as json text
+/// and `None` as NULL.
+///
+/// Panics if a value cannot be serialized, like a map with non-string keys,
+/// see [`try_par!`](crate::try_par!) to handle the error. For named parameters see [`named_par!`](crate::named_par!).
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// conn.query("SELECT * FROM foo where name = ?", par!("fiona")).await?;
+/// conn.execute("UPDATE foo SET age = ? WHERE name = ?", par!(None::<i64>, "fiona")).await?;
+/// # Ok(())
+/// # }
 /// ```
 #[macro_export]
 macro_rules! par {
     ( $( $x:expr ),* ) => {
+        match $crate::try_par!($( $x ),*) {
+            Ok(vec) => vec,
+            Err(e) => panic!("Parameter cannot be serialized: {}", e)
+        }
+    };
+}
+
+/// Specify parameters for parameterized statements, returning an error if a value cannot be serialized
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// # let (name, settings) = ("fiona", serde_json::json!({"theme": "dark"}));
+/// let params = try_par!(name, settings)?;
+/// conn.execute("INSERT INTO foo(name, settings) VALUES (?, ?)", params).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! try_par {
+    ( $( $x:expr ),* ) => {
+        {
+            let vec: Vec<Result<$crate::Value, $crate::__private::JsonError>> = vec![$( $crate::__param!($x) ),*];
+            vec.into_iter().collect::<Result<Vec<$crate::Value>, $crate::__private::JsonError>>()
+        }
+    };
+}
+
+/// Specify named parameters for parameterized statements.
+///
+/// Parameter names are used without the leading `:`, `@` or `$` in the query.
+///
+/// Panics if a value cannot be serialized, see [`try_named_par!`](crate::try_named_par!) to handle the error.
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// conn.query_named("SELECT * FROM foo where name = :name", named_par!{"name" => "fiona"}).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! named_par {
+    ( $( $name:expr => $x:expr ),* $(,)? ) => {
+        match $crate::try_named_par!{$( $name => $x ),*} {
+            Ok(map) => map,
+            Err(e) => panic!("Parameter cannot be serialized: {}", e)
+        }
+    };
+}
+
+/// Specify named parameters for parameterized statements, returning an error if a value cannot be serialized
+/// ```no_run
+/// # use rqlite::*;
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # async fn run() -> Result<(), Error> {
+/// # let conn = ConnectOptions::new("127.0.0.1", 4001).connect().await?;
+/// # let (name, settings) = ("fiona", serde_json::json!({"theme": "dark"}));
+/// let params = try_named_par!{"name" => name, "settings" => settings}?;
+/// conn.execute_named("INSERT INTO foo(name, settings) VALUES (:name, :settings)", params).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! try_named_par {
+    ( $( $name:expr => $x:expr ),* $(,)? ) => {
         {
-            let mut vec: Vec<$crate::Value> = Vec::new();
-            $(
-                vec.push($crate::to_value($x)?);
-            )*
-            vec
+            let vec: Vec<(String, Result<$crate::Value, $crate::__private::JsonError>)> = vec![$( ($name.to_string(), $crate::__param!($x)) ),*];
+            vec.into_iter()
+                .map(|(name, value)| value.map(|value| (name, value)))
+                .collect::<Result<$crate::Map<String, $crate::Value>, $crate::__private::JsonError>>()
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Tagged {
+        tags: Vec<u32>,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+        meta: Option<std::collections::BTreeMap<String, u8>>
+    }
+
+    #[test]
+    fn arrays_are_json_text() {
+        let params = Params::from_serialize(&Tagged { tags: vec![1, 2, 3], data: vec![], meta: None }).unwrap();
+        assert_eq!(params["tags"], serde_json::json!("[1,2,3]"));
+        assert_eq!(params["meta"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn bytes_are_blobs() {
+        let params = Params::from_serialize(&Tagged { tags: vec![], data: vec![1, 2, 3], meta: None }).unwrap();
+        assert_eq!(params["data"], serde_json::json!(base64::encode([1, 2, 3])));
+        assert_eq!(params["tags"], serde_json::json!("[]"));
+    }
+
+    #[test]
+    fn parameters_must_be_a_map() {
+        assert!(matches!(Params::from_serialize(&[1, 2]), Err(RqliteError::DataSer(_))));
+        let mut map = std::collections::HashMap::new();
+        map.insert(1, "a");
+        assert_eq!(Params::from_serialize(&map).unwrap()["1"], serde_json::json!("a"));
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use futures_core::Stream;
+use tokio::sync::mpsc;
+use crate::connect::{Connection, Node};
+use crate::error::RqliteError;
+
+/// Change in the cluster topology, see [`Connection::watch_cluster()`]
+#[derive(Debug)]
+pub enum ClusterEvent {
+    /// Node joined the cluster
+    NodeAdded(Node),
+    /// Node left the cluster
+    NodeRemoved(Node),
+    /// Node can't be reached by the leader anymore
+    NodeUnreachable(Node),
+    /// Node can be reached by the leader again
+    NodeReachable(Node),
+    /// Leader changed, None when the cluster has no leader
+    LeaderChanged(Option<Node>),
+    /// Polling `/nodes` failed, the watcher keeps polling
+    Error(RqliteError)
+}
+
+/// Stream of [`ClusterEvent`], polling stops when it is dropped
+#[derive(Debug)]
+pub struct ClusterWatcher {
+    events: mpsc::Receiver<ClusterEvent>
+}
+
+impl Stream for ClusterWatcher {
+    type Item = ClusterEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ClusterEvent>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl Connection {
+    /// Watch cluster topology, polling `/nodes` every `interval` in a background task
+    ///
+    /// The first poll reports every node as added, along with the current leader.
+    /// ```no_run
+    /// # use rqlite::*;
+    /// # use std::time::Duration;
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// # async fn run() -> Result<(), Error> {
+    /// use futures::StreamExt;
+    /// use rqlite::ClusterEvent;
+    ///
+    /// let conn = ConnectOptions::new("127.0.0.1", 4001)
+    ///        .connect().await?;
+    /// let mut events = conn.watch_cluster(Duration::from_secs(5));
+    /// while let Some(event) = events.next().await {
+    ///     if let ClusterEvent::LeaderChanged(leader) = event {
+    ///         println!("new leader: {:?}", leader);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn watch_cluster(&self, interval: Duration) -> ClusterWatcher {
+        let (sender, events) = mpsc::channel(64);
+        tokio::spawn(watch(self.clone(), interval, sender));
+        ClusterWatcher { events }
+    }
+}
+
+/// Poll `/nodes` every `interval` and send the changes, until the watcher is dropped
+async fn watch(conn: Connection, interval: Duration, sender: mpsc::Sender<ClusterEvent>) {
+    let mut ticks = tokio::time::interval(interval);
+    let mut known: HashMap<String, Node> = HashMap::new();
+    let mut leader: Option<String> = None;
+    loop {
+        let nodes = tokio::select! {
+            _ = sender.closed() => return,
+            nodes = async {
+                ticks.tick().await;
+                conn.nodes(true).await
+            } => nodes
+        };
+        let nodes = match nodes {
+            Ok(v) => v,
+            Err(e) => {
+                if sender.send(ClusterEvent::Error(e)).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+        };
+        for event in diff(&mut known, &mut leader, nodes) {
+            if sender.send(event).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Events between `known` nodes and `nodes`, updating `known` and `leader`
+fn diff(known: &mut HashMap<String, Node>, leader: &mut Option<String>, nodes: Vec<Node>) -> Vec<ClusterEvent> {
+    let mut events = Vec::new();
+    let mut current: HashMap<String, Node> = nodes.into_iter().map(|n| (n.id.clone(), n)).collect();
+
+    let mut removed: Vec<String> = known.keys().filter(|id| !current.contains_key(*id)).cloned().collect();
+    removed.sort();
+    for id in removed {
+        if let Some(node) = known.remove(&id) {
+            events.push(ClusterEvent::NodeRemoved(node));
+        }
+    }
+    let mut ids: Vec<String> = current.keys().cloned().collect();
+    ids.sort();
+    for id in &ids {
+        let node = &current[id];
+        match known.get(id) {
+            None => events.push(ClusterEvent::NodeAdded(node.clone())),
+            Some(old) if old.reachable && !node.reachable => events.push(ClusterEvent::NodeUnreachable(node.clone())),
+            Some(old) if !old.reachable && node.reachable => events.push(ClusterEvent::NodeReachable(node.clone())),
+            _ => {}
+        }
+    }
+
+    let new_leader = current.values().find(|n| n.leader).map(|n| n.id.clone());
+    if new_leader != *leader {
+        events.push(ClusterEvent::LeaderChanged(new_leader.as_ref().map(|id| current[id].clone())));
+        *leader = new_leader;
+    }
+    known.clear();
+    known.extend(current.drain());
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use hyper::{Body, Response, Server};
+    use hyper::service::{make_service_fn, service_fn};
+    use crate::connect::ConnectOptions;
+
+    const NODES: &str = r#"{"1":{"api_addr":"http://127.0.0.1:4001","addr":"127.0.0.1:4002","reachable":true,"leader":true,"time":0.0001}}"#;
+
+    /// Node answering every request with the same `/nodes` response
+    async fn connect() -> Connection {
+        let make = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::from(NODES))) }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make);
+        let port = server.local_addr().port();
+        tokio::spawn(server);
+        ConnectOptions::new("127.0.0.1", port).connect().await.unwrap()
+    }
+
+    async fn next(watcher: &mut ClusterWatcher) -> Option<ClusterEvent> {
+        std::future::poll_fn(|cx| Pin::new(&mut *watcher).poll_next(cx)).await
+    }
+
+    #[tokio::test]
+    async fn first_poll_reports_nodes_and_leader() {
+        let mut watcher = connect().await.watch_cluster(Duration::from_millis(10));
+        assert!(matches!(next(&mut watcher).await, Some(ClusterEvent::NodeAdded(node)) if node.id == "1"));
+        assert!(matches!(next(&mut watcher).await, Some(ClusterEvent::LeaderChanged(Some(node))) if node.id == "1"));
+    }
+
+    #[tokio::test]
+    async fn task_ends_when_watcher_dropped() {
+        let (sender, events) = mpsc::channel(64);
+        let task = tokio::spawn(watch(connect().await, Duration::from_millis(10), sender));
+        let mut watcher = ClusterWatcher { events };
+        next(&mut watcher).await;
+        // the cluster is stable, nothing else is sent
+        drop(watcher);
+        tokio::time::timeout(Duration::from_secs(1), task).await
+                .expect("task still polling after the watcher was dropped")
+                .unwrap();
+    }
+}